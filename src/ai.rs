@@ -4,35 +4,300 @@ use crate::monster;
 use rand::{self, Rng};
 
 use std::collections::HashMap;
+use std::fs;
+
+pub const PROFILE_FILE: &str = "./data/ai_profiles.txt";
+pub const TRAINER_FILE: &str = "./data/trainer_ai.txt";
+
+// How strongly a personality pulls the search towards its preferred actions (in health %)
+const PERSONALITY_BIAS: f64 = 10.0;
+
+/// The general play style of a trainer, layered on top of the chosen difficulty
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Personality {
+    Balanced,
+    Aggressive,
+    SetupFocused,
+    SwitchHappy,
+    Random,
+}
+
+/// A scripted behavior that is checked before the regular decision making
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptRule {
+    /// Use a move that lowers the opponent's stats on the first turn
+    OpenWithStatLowering,
+    /// Switch to a better matchup when the player's lead has a super effective move
+    SwitchOnDisadvantage,
+    /// Use a stat-raising move while the lead is still above 70% health
+    SetupWhileHealthy,
+    /// Use the strongest attack once the player's lead drops below 25% health
+    FinishLowHealth,
+}
+
+/// The AI profile used by a trainer; loaded from `data/ai_profiles.txt`
+#[derive(Debug, Clone)]
+pub struct AiProfile {
+    pub name: String,
+    pub personality: Personality,
+    pub script: Vec<ScriptRule>,
+}
+
+impl AiProfile {
+    /// The profile used when a trainer has no entry in the data files
+    pub fn balanced() -> AiProfile {
+        AiProfile {
+            name: String::from("balanced"),
+            personality: Personality::Balanced,
+            script: Vec::new(),
+        }
+    }
+}
+
+fn parse_personality(s: &str, line_number: usize) -> Result<Personality, String> {
+    match s.trim() {
+        "balanced" => Ok(Personality::Balanced),
+        "aggressive" => Ok(Personality::Aggressive),
+        "setup" => Ok(Personality::SetupFocused),
+        "switch" => Ok(Personality::SwitchHappy),
+        "random" => Ok(Personality::Random),
+        s => Err(format!("line {}: there is no {} personality", line_number, s)),
+    }
+}
+
+fn parse_rule(s: &str, line_number: usize) -> Result<ScriptRule, String> {
+    match s.trim() {
+        "open_stat_lower" => Ok(ScriptRule::OpenWithStatLowering),
+        "switch_disadvantage" => Ok(ScriptRule::SwitchOnDisadvantage),
+        "setup_while_healthy" => Ok(ScriptRule::SetupWhileHealthy),
+        "finish_low_health" => Ok(ScriptRule::FinishLowHealth),
+        s => Err(format!("line {}: there is no {} script rule", line_number, s)),
+    }
+}
+
+/// Loads every AI profile, keyed by profile name
+///
+/// The file has one `profile_name,personality,script` record per line after a header line,
+/// where the script is a `;` separated list of rules and may be empty.
+pub fn load_profiles(path: &str) -> Result<HashMap<String, AiProfile>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+    let mut profiles = HashMap::new();
+
+    // Skip the header line
+    for (index, line) in contents.lines().enumerate().skip(1) {
+        let line_number = index + 1;
+        let v = line.trim().split(',').collect::<Vec<&str>>();
+        match v[..] {
+            [""] => {}
+            [name, personality, script] => {
+                let profile = AiProfile {
+                    name: name.trim().to_string(),
+                    personality: parse_personality(personality, line_number)
+                        .map_err(|e| format!("{}: {}", path, e))?,
+                    script: script
+                        .split(';')
+                        .filter(|rule| !rule.trim().is_empty())
+                        .map(|rule| parse_rule(rule, line_number))
+                        .collect::<Result<Vec<ScriptRule>, String>>()
+                        .map_err(|e| format!("{}: {}", path, e))?,
+                };
+                profiles.insert(profile.name.clone(), profile);
+            }
+            _ => return Err(format!("{}: line {}: cannot read {}", path, line_number, line)),
+        }
+    }
+    Ok(profiles)
+}
+
+/// Loads which AI profile each trainer uses, keyed by trainer id
+///
+/// The file has one `trainer,profile` record per line after a header line, and every profile
+/// must be one of `profiles`.
+pub fn load_trainer_profiles(
+    path: &str,
+    profiles: &HashMap<String, AiProfile>,
+) -> Result<HashMap<String, String>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+    let mut trainers = HashMap::new();
+
+    // Skip the header line
+    for (index, line) in contents.lines().enumerate().skip(1) {
+        let line_number = index + 1;
+        let v = line.trim().split(',').collect::<Vec<&str>>();
+        match v[..] {
+            [""] => {}
+            [trainer, profile] => {
+                let profile = profile.trim();
+                if !profiles.contains_key(profile) {
+                    return Err(format!("{}: line {}: there is no {} AI profile", path, line_number, profile));
+                }
+                trainers.insert(trainer.trim().to_string(), profile.to_string());
+            }
+            _ => return Err(format!("{}: line {}: cannot read {}", path, line_number, line)),
+        }
+    }
+    Ok(trainers)
+}
+
+/// Looks up the profile of a trainer, falling back to a balanced profile
+///
+/// * `profiles` - All loaded AI profiles
+/// * `trainers` - Maps trainer ids onto profile names
+/// * `trainer` - The id of the trainer being battled
+pub fn trainer_profile(
+    profiles: &HashMap<String, AiProfile>,
+    trainers: &HashMap<String, String>,
+    trainer: &str,
+) -> AiProfile {
+    trainers
+        .get(trainer)
+        .and_then(|p| profiles.get(p))
+        .cloned()
+        .unwrap_or_else(AiProfile::balanced)
+}
 
 /// Returns an integer corresponding to an action the AI will take based upon the difficulty
 ///
 /// [0-3] represent that the AI will attack with one of its moves
 /// [4-8] represent that the AI will switch into another monster
+///
+/// Scripted rules of the profile take priority; otherwise the personality biases the
+/// choice made at the given difficulty.
 /// 
 /// * `difficulty` - The difficulty of the AI. 0 is random; 1 is small lookahead; 2 is large lookahead
+/// * `profile` - The AI profile of the trainer being battled
 /// * `monsters` - Maps strings onto their Monster objects; needed for damage calculation
 /// * `state` - The current state of the battle
 pub fn ai_agent(
     difficulty: usize,
+    profile: &AiProfile,
     monsters: &HashMap<String, monster::Monster>,
     battle_state: &mut monster::BattleState,
 ) -> usize {
-    if difficulty == 0 || matches!(battle_state.battle_type, monster::BattleType::Wild) {
+    if matches!(battle_state.battle_type, monster::BattleType::Wild) {
         return rand::thread_rng().gen_range(0..4) as usize;
-    } else if difficulty == 1 {
-        match alphabeta(&monsters, battle_state, 5,  -f64::INFINITY, f64::INFINITY, true).1 {
-            Some(s) => {s}
-            None => 0
+    }
+
+    for rule in profile.script.iter() {
+        if let Some(action) = scripted_action(*rule, monsters, battle_state) {
+            return action;
+        }
+    }
+
+    if difficulty == 0 || profile.personality == Personality::Random {
+        let candidates = preferred_actions(profile.personality, monsters, battle_state);
+        return candidates[rand::thread_rng().gen_range(0..candidates.len())];
+    }
+
+    let depth = if difficulty == 1 { 5 } else { 8 };
+    let mut best: (f64, usize) = (-f64::INFINITY, 0);
+    for action in 0..=(3 + num_switchable_mons(&battle_state.enemy_team)) {
+        let mut new_state = apply_enemy_action(monsters, battle_state, action);
+        // The best payoff so far is carried as alpha, less this action's bias, so later
+        // actions that cannot beat it are pruned
+        let bias = personality_bias(profile.personality, monsters, battle_state, action);
+        let value = alphabeta(monsters, &mut new_state, depth - 1, best.0 - bias, f64::INFINITY, false).0 + bias;
+        if value > best.0 {
+            best = (value, action);
         }
+    }
+    best.1
+}
+
+/// Returns the actions a personality would pick from when choosing at random
+fn preferred_actions(
+    personality: Personality,
+    monsters: &HashMap<String, monster::Monster>,
+    state: &monster::BattleState,
+) -> Vec<usize> {
+    let preferred: Vec<usize> = match personality {
+        Personality::Aggressive => (0..4).filter(|i| state.enemy_move(monsters, *i).damage > 0).collect(),
+        Personality::SetupFocused => (0..4).filter(|i| is_setup_move(state.enemy_move(monsters, *i))).collect(),
+        Personality::SwitchHappy => (0..=(3 + num_switchable_mons(&state.enemy_team))).collect(),
+        _ => Vec::new(),
+    };
+    if preferred.is_empty() {
+        (0..4).collect()
     } else {
-        match alphabeta(&monsters, battle_state, 8,  -f64::INFINITY, f64::INFINITY, true).1 {
-            Some(s) => {s}
-            None => 0
+        preferred
+    }
+}
+
+/// Returns a bonus added to the search payoff of an action that suits the personality
+fn personality_bias(
+    personality: Personality,
+    monsters: &HashMap<String, monster::Monster>,
+    state: &monster::BattleState,
+    action: usize,
+) -> f64 {
+    if action > 3 {
+        return if personality == Personality::SwitchHappy { PERSONALITY_BIAS } else { 0.0 };
+    }
+    let mov = state.enemy_move(monsters, action);
+    match personality {
+        Personality::Aggressive if mov.damage > 0 => PERSONALITY_BIAS,
+        Personality::SetupFocused if is_setup_move(mov) => PERSONALITY_BIAS,
+        _ => 0.0,
+    }
+}
+
+/// A move that raises the user's stages or lowers the opponent's
+fn is_setup_move(mov: &monster::Move) -> bool {
+    mov.self_attack_stages > 0
+        || mov.self_defense_stages > 0
+        || mov.opp_attack_stages < 0
+        || mov.opp_defense_stages < 0
+}
+
+/// Returns the action dictated by a scripted rule, if the rule applies to the current state
+fn scripted_action(
+    rule: ScriptRule,
+    monsters: &HashMap<String, monster::Monster>,
+    state: &monster::BattleState,
+) -> Option<usize> {
+    let enemy = &monsters[&state.enemy_team[0].0];
+    let player = &monsters[&state.player_team[0].0];
+    let enemy_moves: Vec<&monster::Move> = (0..4).map(|i| state.enemy_move(monsters, i)).collect();
+    match rule {
+        ScriptRule::OpenWithStatLowering => {
+            if state.enemy_turns > 0 {
+                return None;
+            }
+            (0..4).find(|i| enemy_moves[*i].opp_attack_stages < 0 || enemy_moves[*i].opp_defense_stages < 0)
+        }
+        ScriptRule::SwitchOnDisadvantage => {
+            if !has_super_effective(player, &enemy.monster_type) {
+                return None;
+            }
+            (1..state.enemy_team.len())
+                .filter(|i| state.enemy_team[*i].1 > 0.0)
+                .find(|i| !has_super_effective(player, &monsters[&state.enemy_team[*i].0].monster_type))
+                .map(|i| i + 3)
+        }
+        ScriptRule::SetupWhileHealthy => {
+            if state.enemy_team[0].1 <= 70.0 || state.opp_attack_stages >= 2 {
+                return None;
+            }
+            (0..4).find(|i| enemy_moves[*i].self_attack_stages > 0 || enemy_moves[*i].self_defense_stages > 0)
+        }
+        ScriptRule::FinishLowHealth => {
+            if state.player_team[0].1 >= 25.0 {
+                return None;
+            }
+            (0..4)
+                .filter(|i| enemy_moves[*i].damage > 0)
+                .max_by_key(|i| enemy_moves[*i].damage)
         }
     }
 }
-    
+
+/// Whether any damaging move of `attacker` is super effective against `defense_type`
+fn has_super_effective(attacker: &monster::Monster, defense_type: &String) -> bool {
+    attacker
+        .moves
+        .iter()
+        .any(|m| m.damage > 0 && monster::type_effectiveness(&m.attack_type, defense_type) > 1.0)
+}
 
 /// Returns the sum of health percentages (0-100) for all team monsters
 ///
@@ -62,6 +327,54 @@ fn num_switchable_mons(team: &Vec<(String, f32, usize)>) -> usize {
     return if alive_mons == 0 { 0 } else { alive_mons - 1 };
 }
 
+/// Returns the state following an action of the AI (maximizing player)
+///
+/// * `monsters` - Maps strings onto their Monster objects; needed for damage calculation
+/// * `state` - The current state of the battle
+/// * `action` - The action taken; see `ai_agent`
+fn apply_enemy_action<'a>(
+    monsters: &HashMap<String, monster::Monster>,
+    state: &monster::BattleState<'a>,
+    action: usize,
+) -> monster::BattleState<'a> {
+    // Create a new state to update based upon the action taken
+    let mut new_state = monster::BattleState {
+        player_turn: !state.player_turn,
+        player_team: state.player_team.clone(),
+        enemy_team: state.enemy_team.clone(),
+        self_attack_stages: state.self_attack_stages,
+        self_defense_stages: state.self_defense_stages,
+        opp_attack_stages: state.opp_attack_stages,
+        opp_defense_stages: state.opp_defense_stages,
+        player_badges: state.player_badges,
+        battle_type: state.battle_type,
+        enemy_turns: state.enemy_turns,
+        enemy_movesets: state.enemy_movesets,
+    };
+
+    // Change the new state based upon the action (attack or switch in another monster)
+    if action < 4 {
+        // Action corresponding to a move
+
+        // Calculate the new health of the opponent (player)
+        let mut new_health = new_state.player_team[0].1
+            - monster::calculate_damage(monsters, &mut new_state, action);
+        new_health = new_health.clamp(0.0, 100.0);
+        new_state.player_team[0].1 = new_health;
+
+        // Makes sure an active monster is still in front after attack
+        new_state.player_team = battle::verify_team(&new_state.player_team);
+    } else {
+        // Action corresponding to a switch
+        new_state.enemy_team.swap(0, action - 3);
+        new_state.opp_attack_stages = 0;
+        new_state.opp_defense_stages = 0;
+        new_state.enemy_team = battle::verify_team(&new_state.enemy_team);
+    }
+
+    new_state
+}
+
 /// Runs the α-β algorithm and returns the payoff and action for the optimal path of play
 ///
 /// * `monsters` - Maps strings onto their Monster objects; needed for damage calculation
@@ -108,37 +421,7 @@ pub fn alphabeta(
             let temp = value;
 
             // Create a new state to update based upon the action taken
-            let mut new_state = monster::BattleState {
-                player_turn: !state.player_turn,
-                player_team: state.player_team.clone(),
-                enemy_team: state.enemy_team.clone(),
-                self_attack_stages: state.self_attack_stages,
-                self_defense_stages: state.self_defense_stages,
-                opp_attack_stages: state.opp_attack_stages,
-                opp_defense_stages: state.opp_defense_stages,
-                player_badges: state.player_badges,
-                battle_type: state.battle_type,
-            };
-
-            // Change the new state based upon the action (attack or switch in another monster)
-            if action < 4 {
-                // Action corresponding to a move
-
-                // Calculate the new health of the opponent (player)
-                let mut new_health = new_state.player_team[0].1
-                    - monster::calculate_damage(monsters, &mut new_state, action);
-                new_health = new_health.clamp(0.0, 100.0);
-                new_state.player_team[0].1 = new_health;
-
-                // Makes sure an active monster is still in front after attack
-                new_state.player_team = battle::verify_team(&new_state.player_team);
-            } else {
-                // Action corresponding to a switch
-                new_state.enemy_team.swap(0, action - 3);
-                new_state.opp_attack_stages = 0;
-                new_state.opp_defense_stages = 0;
-                new_state.enemy_team = battle::verify_team(&new_state.enemy_team);
-            }
+            let mut new_state = apply_enemy_action(monsters, state, action);
 
             // Following our move, find out which one leads to the best payoff by traversing the game tree
             value = value.max(alphabeta(monsters, &mut new_state, depth - 1, alpha, beta, false).0);
@@ -178,6 +461,8 @@ pub fn alphabeta(
                 opp_defense_stages: state.opp_defense_stages,
                 player_badges: state.player_badges,
                 battle_type: state.battle_type,
+                enemy_turns: state.enemy_turns,
+                enemy_movesets: state.enemy_movesets,
             };
 
             // Change the new state based upon the action (attack or switch in another monster)
@@ -0,0 +1,154 @@
+use sdl2::image::LoadTexture;
+use sdl2::pixels::Color;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::WindowContext;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const FONT_PATH: &str = "./fonts/framd.ttf";
+const FONT_SIZE: u16 = 256;
+
+// Every image the game draws besides the monsters, the maps and the sprites; all of them have to be there to start
+const IMAGES: [&str; 5] = [
+    "images/battle_bg.png",
+    "images/welcome.png",
+    "images/difficulty_select.png",
+    "images/walk1_32.png",
+    "images/start.png",
+];
+
+// Once this many text textures are cached, the cache starts over so it cannot grow forever
+const TEXT_CACHE_LIMIT: usize = 512;
+
+// Text textures, by the text and the color it was rendered in
+type TextCache<'a> = HashMap<(String, (u8, u8, u8, u8)), Rc<Texture<'a>>>;
+
+/// Refers to an image loaded by the `AssetManager`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureId(usize);
+
+/// Loads every image and the font once, and keeps the text that has been drawn
+///
+/// Images are looked up by path and handed out as `TextureId`s; text is rendered the first time
+/// it is drawn in a color and reused after that.
+pub struct AssetManager<'a> {
+    texture_creator: &'a TextureCreator<WindowContext>,
+    font: Font<'a, 'static>,
+    textures: Vec<Texture<'a>>,
+    ids: HashMap<String, TextureId>,
+    text: RefCell<TextCache<'a>>,
+}
+
+impl<'a> AssetManager<'a> {
+    /// Loads the font, every image the game needs and the extra images given
+    ///
+    /// Fails with a single error listing every asset that could not be loaded.
+    ///
+    /// * `images` - Images needed on top of the built in ones, like the monsters and map tiles
+    pub fn load(
+        texture_creator: &'a TextureCreator<WindowContext>,
+        ttf_context: &'a Sdl2TtfContext,
+        images: &[String],
+    ) -> Result<AssetManager<'a>, String> {
+        let mut missing = Vec::new();
+        let font = ttf_context.load_font(FONT_PATH, FONT_SIZE);
+        if let Err(e) = &font {
+            missing.push(format!("{} ({})", FONT_PATH, e));
+        }
+
+        let mut textures = Vec::new();
+        let mut ids = HashMap::new();
+        for path in IMAGES.iter().copied().chain(images.iter().map(|p| p.as_str())) {
+            if ids.contains_key(path) {
+                continue;
+            }
+            match texture_creator.load_texture(path) {
+                Ok(texture) => {
+                    ids.insert(path.to_string(), TextureId(textures.len()));
+                    textures.push(texture);
+                }
+                Err(e) => missing.push(format!("{} ({})", path, e)),
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(format!("Missing assets:\n\t\t\t{}", missing.join("\n\t\t\t")));
+        }
+
+        Ok(AssetManager {
+            texture_creator,
+            font: font?,
+            textures,
+            ids,
+            text: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Loads images that the game can do without, skipping any that are not there
+    pub fn load_optional(&mut self, images: &[String]) {
+        for path in images.iter() {
+            if self.ids.contains_key(path) {
+                continue;
+            }
+            if let Ok(texture) = self.texture_creator.load_texture(path) {
+                self.ids.insert(path.clone(), TextureId(self.textures.len()));
+                self.textures.push(texture);
+            }
+        }
+    }
+
+    pub fn font(&self) -> &Font<'a, 'static> {
+        &self.font
+    }
+
+    /// The handle of a loaded image, if it was loaded
+    pub fn find(&self, path: &str) -> Option<TextureId> {
+        self.ids.get(path).copied()
+    }
+
+    /// The handle of an image loaded at startup
+    pub fn id(&self, path: &str) -> TextureId {
+        match self.find(path) {
+            Some(id) => id,
+            None => panic!("{} was not loaded at startup", path),
+        }
+    }
+
+    pub fn get(&self, id: TextureId) -> &Texture<'a> {
+        &self.textures[id.0]
+    }
+
+    /// The image at `path`, which has to have been loaded at startup
+    pub fn texture(&self, path: &str) -> &Texture<'a> {
+        self.get(self.id(path))
+    }
+
+    /// `text` rendered in `color`, rendering it only the first time it is asked for
+    pub fn text(&self, text: &str, color: Color) -> Result<Rc<Texture<'a>>, String> {
+        let key = (text.to_string(), color.rgba());
+        if let Some(texture) = self.text.borrow().get(&key) {
+            return Ok(Rc::clone(texture));
+        }
+
+        let surface = self
+            .font
+            .render(text)
+            .blended(color)
+            .map_err(|e| e.to_string())?;
+        let texture = Rc::new(
+            self.texture_creator
+                .create_texture_from_surface(&surface)
+                .map_err(|e| e.to_string())?,
+        );
+
+        let mut cache = self.text.borrow_mut();
+        if cache.len() >= TEXT_CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(key, Rc::clone(&texture));
+        Ok(texture)
+    }
+}
@@ -0,0 +1,243 @@
+use crate::ai;
+use crate::input::Action;
+use crate::monster;
+use crate::scene::{Game, SceneKind};
+
+use rand::{self, Rng};
+
+use std::backtrace::Backtrace;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::panic;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CRASH_DIR: &str = "crash_reports";
+
+// Difficulty of the search used to pick the player's moves
+const AUTOPLAY_DIFFICULTY: usize = 1;
+
+// Chance that the bot opens the team menu instead of acting normally
+const MENU_CHANCE: f64 = 0.02;
+
+// Chance that the bot decides to leave the gym it is walking around in
+const EXIT_CHANCE: f64 = 0.1;
+
+const WALK_ACTIONS: [Action; 4] = [Action::Up, Action::Left, Action::Down, Action::Right];
+const MENU_ACTIONS: [Action; 5] = [Action::Up, Action::Left, Action::Down, Action::Right, Action::Confirm];
+
+// The last known state of the game, written into crash reports
+static LAST_CONTEXT: Mutex<String> = Mutex::new(String::new());
+
+/// Drives the player's input when the game runs with `--autoplay`
+///
+/// Input is produced as a set of held actions each frame, exactly like the keyboard and
+/// controllers, so the game goes through the same code paths as when a person is playing.
+pub struct Autoplay {
+    walk_action: Action,
+    walk_frames: u32,
+    wants_exit: bool,
+    menu_presses: u32,
+    planned_move: Option<usize>,
+    tapped: bool,
+    profile: ai::AiProfile,
+}
+
+impl Autoplay {
+    pub fn new() -> Autoplay {
+        Autoplay {
+            walk_action: Action::Down,
+            walk_frames: 0,
+            wants_exit: false,
+            menu_presses: 0,
+            planned_move: None,
+            tapped: false,
+            profile: ai::AiProfile::balanced(),
+        }
+    }
+
+    /// Returns the actions held this frame
+    ///
+    /// Menus only react to fresh presses, so discrete presses are alternated with a frame
+    /// where nothing is tapped.
+    ///
+    /// * `scene` - The kind of scene on top of the scene stack
+    /// * `game` - The shared game state; needed to plan moves
+    pub fn actions(&mut self, scene: SceneKind, game: &Game) -> HashSet<Action> {
+        let mut actions = HashSet::new();
+        if matches!(scene, SceneKind::Overworld | SceneKind::Gym) {
+            self.walk(&mut actions);
+        }
+
+        // Every other frame nothing is tapped so that the next tap counts as a new press
+        if self.tapped {
+            self.tapped = false;
+            return actions;
+        }
+
+        let tap = match scene {
+            SceneKind::Intro => Some(random_action(&[Action::Up, Action::Down, Action::Confirm, Action::Confirm])),
+            SceneKind::Battle(current_choice) => Some(self.battle_action(game.monsters, &game.battle_state, current_choice)),
+            SceneKind::TeamMenu(menu_choice) => Some(self.menu_action(menu_choice)),
+            SceneKind::Overworld | SceneKind::Gym => self.open_menu(Action::Menu),
+            SceneKind::Options | SceneKind::Journal => Some(Action::Cancel),
+            SceneKind::BadgeCase => Some(Action::Confirm),
+            SceneKind::Dialogue => Some(random_action(&[Action::Up, Action::Down, Action::Confirm, Action::Confirm])),
+            SceneKind::Shop => Some(random_action(&[Action::Up, Action::Down, Action::Left, Action::Confirm, Action::Confirm, Action::Cancel])),
+            SceneKind::Credits | SceneKind::Healing => None,
+        };
+
+        if let Some(a) = tap {
+            actions.insert(a);
+            self.tapped = true;
+        }
+        actions
+    }
+
+    /// Holds a walking direction for a random amount of frames, tapping Interact along the way
+    fn walk(&mut self, actions: &mut HashSet<Action>) {
+        let mut rng = rand::thread_rng();
+        if self.walk_frames == 0 {
+            self.walk_action = random_action(&WALK_ACTIONS);
+            self.walk_frames = rng.gen_range(20..120);
+            self.wants_exit = rng.gen_bool(EXIT_CHANCE);
+        }
+        self.walk_frames -= 1;
+
+        actions.insert(self.walk_action);
+        // Talk to anyone that is bumped into and knock on any door that is walked up to; the
+        // buttons are let go every other frame so that each frame they are down is a new press
+        if self.walk_frames.is_multiple_of(2) {
+            actions.insert(Action::Interact);
+            if self.wants_exit {
+                actions.insert(Action::Cancel);
+            }
+        }
+    }
+
+    /// Starts wandering through the team menu every once in a while
+    fn open_menu(&mut self, action: Action) -> Option<Action> {
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(MENU_CHANCE) {
+            self.menu_presses = rng.gen_range(1..8);
+            Some(action)
+        } else {
+            None
+        }
+    }
+
+    /// Presses random buttons in the team menu, then heads for the OK button and closes it
+    fn menu_action(&mut self, menu_choice: usize) -> Action {
+        if self.menu_presses > 0 {
+            self.menu_presses -= 1;
+            random_action(&MENU_ACTIONS)
+        } else if menu_choice != 6 {
+            Action::Down
+        } else {
+            Action::Confirm
+        }
+    }
+
+    /// Moves the cursor onto the move planned by the AI and uses it
+    fn battle_action(
+        &mut self,
+        monsters: &HashMap<String, monster::Monster>,
+        battle_state: &monster::BattleState,
+        current_choice: usize,
+    ) -> Action {
+        if let Some(a) = self.open_menu(Action::Down) {
+            return a;
+        }
+
+        let planned = match self.planned_move {
+            Some(m) => m,
+            None => {
+                let m = self.plan_move(monsters, battle_state);
+                self.planned_move = Some(m);
+                m
+            }
+        };
+
+        if current_choice != planned {
+            Action::Right
+        } else {
+            self.planned_move = None;
+            Action::Confirm
+        }
+    }
+
+    /// Runs the battle AI from the player's side of the battle to pick one of the lead's moves
+    fn plan_move(
+        &self,
+        monsters: &HashMap<String, monster::Monster>,
+        battle_state: &monster::BattleState,
+    ) -> usize {
+        let mut mirrored = monster::BattleState {
+            player_turn: false,
+            player_team: battle_state.enemy_team.clone(),
+            enemy_team: battle_state.player_team.clone(),
+            self_attack_stages: battle_state.opp_attack_stages,
+            self_defense_stages: battle_state.opp_defense_stages,
+            opp_attack_stages: battle_state.self_attack_stages,
+            opp_defense_stages: battle_state.self_defense_stages,
+            player_badges: battle_state.player_badges,
+            battle_type: &monster::BattleType::Trainer,
+            enemy_turns: 1,
+            enemy_movesets: None,
+        };
+        let action = ai::ai_agent(AUTOPLAY_DIFFICULTY, &self.profile, monsters, &mut mirrored);
+
+        // Switching is done through the team menu, so only moves are planned here
+        if action < 4 {
+            action
+        } else {
+            rand::thread_rng().gen_range(0..4)
+        }
+    }
+}
+
+impl Default for Autoplay {
+    fn default() -> Self {
+        Autoplay::new()
+    }
+}
+
+fn random_action(actions: &[Action]) -> Action {
+    actions[rand::thread_rng().gen_range(0..actions.len())]
+}
+
+/// Records a description of the current game state to include in crash reports
+pub fn record_context(context: String) {
+    let mut last = LAST_CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    *last = context;
+}
+
+/// Writes a crash report into `crash_reports/` for every panic
+pub fn install_crash_reporter() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        write_crash_report(&info.to_string());
+        default_hook(info);
+    }));
+}
+
+/// Writes a crash report with the message, the last recorded state and a backtrace
+pub fn write_crash_report(message: &str) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let context = LAST_CONTEXT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let report = format!(
+        "{}\n\nLast state:\n{}\n\nBacktrace:\n{}\n",
+        message,
+        context,
+        Backtrace::force_capture()
+    );
+
+    let path = format!("{}/crash-{}.txt", CRASH_DIR, secs);
+    match fs::create_dir_all(CRASH_DIR).and_then(|_| fs::write(&path, report)) {
+        Ok(_) => println!("\n\t\tCrash report written to {}", path),
+        Err(e) => println!("\n\t\tFailed to write crash report: {}", e),
+    }
+}
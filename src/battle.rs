@@ -1,28 +1,45 @@
 use sdl2::pixels::Color;
 use sdl2::render::TextureQuery;
-use sdl2::image::LoadTexture;
 use sdl2::rect::Rect;
 
-use std::time::Duration;
-use std::thread;
-use std::collections::HashMap;
 
-use crate::monster;
+use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
 
-pub enum Map {
-    Intro,
-    Overworld,
-    Battle,
-    GymOne,
-    GymTwo,
-    GymThree,
-    GymFour,
-    Gym,
+use crate::ai;
+use crate::assets::AssetManager;
+use crate::camera::{CAM_W, CAM_H};
+use crate::credits;
+use crate::dialogue::DialogueScene;
+use crate::gym;
+use crate::menu;
+use crate::monster;
+use crate::overworld;
+use crate::input::{Action, Input};
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::shop;
+use crate::sprite::{Animator, Sprites};
+use crate::ui;
+
+/// How a battle stands after a turn
+#[derive(Debug, PartialEq)]
+pub enum BattleResult {
+    Ongoing,
+    Victory,
+    Defeat,
 }
 
-const CAM_W: u32 = 1280;
-const CAM_H: u32 = 720;
-const MESSAGE_TIME: u64 = 500;
+// Seconds a message stays up once it is typed out
+const MESSAGE_TIME: f64 = 0.5;
+
+// How many characters of a message are typed out each second
+const TYPE_SPEED: f64 = 60.0;
+
+// How quickly health bars drain and fill, in percent per second
+const DRAIN_SPEED: f32 = 80.0;
+
+// Seconds the screen takes to fade to black when a battle ends
+const FADE_TIME: f64 = 0.8;
 
 fn center(r1: Rect, w: u32, h: u32) -> Rect {
     let mut x = r1.x();
@@ -48,7 +65,7 @@ fn fit(r1: Rect, w: u32, h: u32) -> Rect {
 
 pub fn create_all_attack_textures<'a, T>(
     texture_creator: &'a sdl2::render::TextureCreator<T>, 
-    font: &'a sdl2::ttf::Font,
+    font: &sdl2::ttf::Font,
     attack_names: &'a Vec<String>,
 ) -> Result<HashMap<String, sdl2::render::Texture<'a>>, String> {
 
@@ -71,7 +88,7 @@ pub fn create_all_attack_textures<'a, T>(
 
 pub fn create_all_effect_textures<'a, T>(
     texture_creator: &'a sdl2::render::TextureCreator<T>, 
-    font: &'a sdl2::ttf::Font,
+    font: &sdl2::ttf::Font,
     attack_effects: &'a Vec<String>,
 ) -> Result<HashMap<String, sdl2::render::Texture<'a>>, String> {
 
@@ -94,7 +111,7 @@ pub fn create_all_effect_textures<'a, T>(
 
 pub fn create_all_name_tuples<'a, T>(
     texture_creator: &'a sdl2::render::TextureCreator<T>, 
-    font: &'a sdl2::ttf::Font,
+    font: &sdl2::ttf::Font,
     monster_names: &'a Vec<String>,
 ) -> Result<HashMap<String, (sdl2::render::Texture<'a>, Rect, Rect)>, String> {
     
@@ -129,63 +146,190 @@ pub fn create_all_name_tuples<'a, T>(
     Ok( monster_name_map )
 }
 
-pub fn create_all_monster_textures<'a, T>(
-    texture_creator: &'a sdl2::render::TextureCreator<T>, 
-    monster_names: &'a Vec<String>,
-) -> Result<HashMap<String, sdl2::render::Texture<'a>>, String> {
-    let mut monster_text_map = HashMap::new();
-    
-    for item in monster_names.iter() {
-        let mut a = item.clone();
-        if a.starts_with("​") { // ZERO WIDTH SPACE {
-            let mut chars = a.chars();
-            chars.next();
-            a = chars.collect();
-        }
-        let im_path = format!("images/{}.png", a);
-        let temp_text = texture_creator.load_texture(im_path)?;
-        monster_text_map.insert(item.clone(), temp_text);
-    }
-
-    Ok( monster_text_map )
+/// The image of a monster, which is named after it without any zero width space in front
+pub fn monster_image(name: &str) -> String {
+    format!("images/{}.png", name.trim_start_matches('\u{200b}'))
 }
 
 pub struct Battle<'a> {
-    pub background_texture: &'a sdl2::render::Texture<'a>,
     pub player_name: String,
     pub enemy_name: String,
-    pub font: &'a sdl2::ttf::Font<'a, 'a>,
     pub player_health: f32,
     pub enemy_health: f32,
     pub name_text_map: &'a HashMap<String, (sdl2::render::Texture<'a>, Rect, Rect)>,
     pub attack_map: &'a HashMap<String, sdl2::render::Texture<'a>>,
     pub effect_map: &'a HashMap<String, sdl2::render::Texture<'a>>,
-    pub monster_text_map: &'a HashMap<String, sdl2::render::Texture<'a>>,
     pub moves: &'a HashMap<String, monster::Move>,
     pub monsters: &'a HashMap<String, monster::Monster<'a>>,
+    pub sprites: &'a Sprites,
+    /// The monsters face each other across the field
+    pub player_sprite: Animator,
+    pub enemy_sprite: Animator,
     pub player_level: usize,
     pub opp_level: usize,
+    pub victory_message: Option<String>,
+}
+
+/// One thing shown on the battle screen after a turn
+pub enum Step {
+    /// Types out a message, then leaves it up for a moment
+    Message(String),
+    /// Waits for a number of seconds
+    Pause(f64),
+    /// Drains or fills the player's health bar up to a value
+    PlayerHealth(f32),
+    /// Drains or fills the opponent's health bar up to a value
+    EnemyHealth(f32),
+    /// Shows a new level for the player's monster
+    PlayerLevel(usize),
+    /// Brings out another of the player's monsters
+    PlayerOut { name: String, health: f32, level: usize },
+    /// Brings out another of the opponent's monsters
+    EnemyOut { name: String, health: f32, level: usize },
+    /// Darkens the screen before leaving the battle
+    FadeOut,
+}
+
+/// The steps of a turn waiting to be shown, played one after another while the game keeps running
+///
+/// Turns are worked out all at once; the timeline then brings the battle screen up to date
+/// step by step. Confirm skips ahead through it.
+pub struct Timeline {
+    steps: VecDeque<Step>,
+    elapsed: f64,
 }
 
-impl<'a> Battle<'a> {
-    pub fn apply_player_damage(&mut self, damage: f32) {
-        self.player_health -= damage;
-        self.player_health = self.player_health.clamp(0.0, 100.0);
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline {
+            steps: VecDeque::new(),
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, step: Step) {
+        self.steps.push_back(step);
+    }
+
+    pub fn pause(&mut self, seconds: f64) {
+        self.push(Step::Pause(seconds));
+    }
+
+    pub fn say(&mut self, message: String) {
+        self.push(Step::Message(message));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Plays the timeline for `dt` seconds, showing the result of every step that finishes
+    pub fn advance(&mut self, battle_draw: &mut Battle, dt: f64) {
+        self.elapsed += dt;
+        let mut drain = DRAIN_SPEED * dt as f32;
+        loop {
+            let done = match self.steps.front() {
+                None => break,
+                Some(Step::Message(text)) => self.elapsed >= typing_time(text) + MESSAGE_TIME,
+                Some(Step::Pause(seconds)) => self.elapsed >= *seconds,
+                Some(Step::FadeOut) => self.elapsed >= FADE_TIME,
+                Some(Step::PlayerHealth(target)) => {
+                    battle_draw.player_health = approach(battle_draw.player_health, *target, drain);
+                    battle_draw.player_health == *target
+                }
+                Some(Step::EnemyHealth(target)) => {
+                    battle_draw.enemy_health = approach(battle_draw.enemy_health, *target, drain);
+                    battle_draw.enemy_health == *target
+                }
+                Some(_) => true,
+            };
+            if !done {
+                break;
+            }
+            self.finish(battle_draw);
+            // The time of this update has been used up; only instant steps can follow right away
+            drain = 0.0;
+        }
     }
-    pub fn apply_enemy_damage(&mut self, damage: f32) {
-        self.enemy_health -= damage;
-        self.enemy_health = self.enemy_health.clamp(0.0, 100.0);
+
+    /// Skips ahead: finishes typing the current message, or ends whatever is playing
+    pub fn skip(&mut self, battle_draw: &mut Battle) {
+        if let Some(Step::Message(text)) = self.steps.front() {
+            if self.elapsed < typing_time(text) {
+                self.elapsed = typing_time(text);
+                return;
+            }
+        }
+        self.finish(battle_draw);
+    }
+
+    /// Ends the current step, leaving the battle screen as it should be after it
+    fn finish(&mut self, battle_draw: &mut Battle) {
+        self.elapsed = 0.0;
+        match self.steps.pop_front() {
+            Some(Step::PlayerHealth(health)) => battle_draw.player_health = health,
+            Some(Step::EnemyHealth(health)) => battle_draw.enemy_health = health,
+            Some(Step::PlayerLevel(level)) => battle_draw.player_level = level,
+            Some(Step::PlayerOut { name, health, level }) => {
+                battle_draw.player_name = name;
+                battle_draw.player_health = health;
+                battle_draw.player_level = level;
+            }
+            Some(Step::EnemyOut { name, health, level }) => {
+                battle_draw.enemy_name = name;
+                battle_draw.enemy_health = health;
+                battle_draw.opp_level = level;
+            }
+            _ => {}
+        }
+    }
+
+    /// The part of the current message typed out so far
+    pub fn message(&self) -> Option<String> {
+        match self.steps.front() {
+            Some(Step::Message(text)) => Some(text.chars().take((self.elapsed * TYPE_SPEED) as usize).collect()),
+            _ => None,
+        }
+    }
+
+    /// How far along the closing fade is, if it is playing
+    pub fn fade(&self) -> Option<f64> {
+        match self.steps.front() {
+            Some(Step::FadeOut) => Some(self.elapsed / FADE_TIME),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline::new()
+    }
+}
+
+/// Seconds it takes to type out a message
+fn typing_time(text: &str) -> f64 {
+    text.chars().count() as f64 / TYPE_SPEED
+}
+
+/// Moves `value` towards `target` by at most `step`
+fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if value < target {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
     }
 }
 
 pub fn draw_battle(
     wincan: &mut sdl2::render::WindowCanvas,
+    assets: &AssetManager,
     battle_init: &Battle,
     choice: Option<usize>,
-    message: Option<String>,
+    message: Option<&str>,
 ) -> Result<(), String> {
     // Load the battle scene background
-    wincan.copy(&battle_init.background_texture, None, Rect::new(0,0,CAM_W,CAM_H))?;
+    wincan.copy(assets.texture("images/battle_bg.png"), None, Rect::new(0,0,CAM_W,CAM_H))?;
 
     let move_rects: Vec<_> = (0..4)
         .map(|i| 180 + i * (200 + 40))
@@ -245,36 +389,22 @@ pub fn draw_battle(
     wincan.copy(&battle_init.name_text_map[&battle_init.enemy_name].0, None, battle_init.name_text_map[&battle_init.enemy_name].2)?;
 
     // Add both monsters
-    wincan.copy(&battle_init.monster_text_map[&battle_init.player_name], None, Rect::new(800,275,200,200))?;
-    wincan.copy_ex(&battle_init.monster_text_map[&battle_init.enemy_name], None, Rect::new(280 as i32,25 as i32,200,200), 0 as f64, None, true, false)?;
+    let player_image = assets.texture(&monster_image(&battle_init.player_name));
+    battle_init.player_sprite.draw_texture(wincan, player_image, battle_init.sprites, Rect::new(800,275,200,200))?;
+    let enemy_image = assets.texture(&monster_image(&battle_init.enemy_name));
+    battle_init.enemy_sprite.draw_texture(wincan, enemy_image, battle_init.sprites, Rect::new(280 as i32,25 as i32,200,200))?;
 
     // Add level to enemy monster
-    let texture_creator = wincan.texture_creator();
     let f = format!("Level: {}", &battle_init.opp_level.to_string()); 
-    let surface = battle_init
-        .font
-        .render(&f)
-        .blended(Color::BLACK)
-        .map_err(|e| e.to_string())?;
-    let texture = texture_creator
-        .create_texture_from_surface(&surface)
-        .map_err(|e| e.to_string())?;
+    let texture = assets.text(&f, Color::BLACK)?;
     let TextureQuery { width, height, .. } = texture.query();
     let text_rect = Rect::new(battle_init.name_text_map[&battle_init.enemy_name].2.x, 28, 150, 20);
     let text_rect = fit(text_rect, width, height);
     wincan.copy(&texture, None, text_rect)?;
 
     // Add level to player monster
-    let texture_creator = wincan.texture_creator();
     let f = format!("Level: {}", &battle_init.player_level.to_string()); 
-    let surface = battle_init
-        .font
-        .render(&f)
-        .blended(Color::BLACK)
-        .map_err(|e| e.to_string())?;
-    let texture = texture_creator
-        .create_texture_from_surface(&surface)
-        .map_err(|e| e.to_string())?;
+    let texture = assets.text(&f, Color::BLACK)?;
     let TextureQuery { width, height, .. } = texture.query();
     let text_rect = Rect::new(battle_init.name_text_map[&battle_init.player_name].1.x + battle_init.name_text_map[&battle_init.player_name].1.width() as i32 - width as i32, 452, 150, 20);
     let text_rect = fit(text_rect, width, height);
@@ -288,14 +418,11 @@ pub fn draw_battle(
     match message {
         Some(text) => {
             let a = text.replace("​", ""); // ZERO WIDTH SPACE
-            message_box(wincan, battle_init.font, &a)?;
-            thread::sleep(Duration::from_millis(MESSAGE_TIME));
+            message_box(wincan, assets, &a)?;
         }
         None => (),
     };
 
-    wincan.present();
-
     Ok(())
 }
 
@@ -330,12 +457,11 @@ pub fn health_bars(wincan: &mut sdl2::render::WindowCanvas, player_health: f32,
     Ok(())
 }
 
-fn message_box<'a>(
+fn message_box(
     wincan: &mut sdl2::render::WindowCanvas, 
-    font: &'a sdl2::ttf::Font,
+    assets: &AssetManager,
     message: &str,
 ) -> Result<(), String> {
-    let texture_creator = wincan.texture_creator();
 
     let r2 = Rect::new(600, 150, 400, 125);
     wincan.set_draw_color(Color::WHITE);
@@ -349,232 +475,199 @@ fn message_box<'a>(
     wincan.set_draw_color(Color::WHITE);
     wincan.fill_rect(r2)?;
 
-    let mut line_message = format!("");
-
-    let mut line_number = 0;
-
+    // Wrap the message onto a new line whenever it gets too wide for the box
+    let mut lines = vec![String::new()];
     for c in message.chars() {
-        line_message = format!("{}{}", line_message, c);
-        
-        let mut surface = font
-            .render(&line_message)
-            .blended(Color::BLACK)
-            .map_err(|e| e.to_string())?;
-        let mut texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
-
-        let TextureQuery { width, height, .. } = texture.query();
-
+        let mut line = format!("{}{}", lines[lines.len() - 1], c);
+        let (width, height) = assets.font().size_of(&line).map_err(|e| e.to_string())?;
         if width as f32/height as f32 > 375 as f32/30 as f32 {
-            line_number += 1;
-            line_message = format!("{}", c);
-            surface = font
-                .render(&line_message)
-                .blended(Color::BLACK)
-                .map_err(|e| e.to_string())?;
-            texture = texture_creator
-                .create_texture_from_surface(&surface)
-                .map_err(|e| e.to_string())?;
+            lines.push(String::new());
+            line = format!("{}", c);
+        }
+        let last = lines.len() - 1;
+        lines[last] = line;
+    }
+
+    for (line_number, line) in lines.iter().enumerate() {
+        // Nothing has been typed out yet
+        if line.is_empty() {
+            continue;
         }
+        let texture = assets.text(line, Color::BLACK)?;
 
         let TextureQuery { width, height, .. } = texture.query();
         
-        let text_rect = Rect::new(612, 162 + (line_number * 35), 375, 30);
+        let text_rect = Rect::new(612, 162 + (line_number as i32 * 35), 375, 30);
         let text_rect = fit(text_rect, width, height);
         
         wincan.copy(&texture, None, text_rect)?;
-        wincan.present();
     }
     Ok(())
 }
 
+/// Works out the player's move, queueing up everything that happens on the timeline
+///
+/// * `victory_message` - An extra message shown if this move wins the battle
 pub fn player_battle_turn(
-    wincan: &mut sdl2::render::WindowCanvas,
     battle_state: &mut monster::BattleState,
-    battle_draw: &mut Battle,
     monsters_map: &HashMap<String, monster::Monster>,
     current_choice: usize,
-) -> Result<Map, String> {
-    let enemy_monster = battle_draw.enemy_name.clone();
-    let player_monster = battle_draw.player_name.clone();
+    victory_message: Option<String>,
+    timeline: &mut Timeline,
+) -> BattleResult {
+    let enemy_monster = battle_state.enemy_team[0].0.clone();
+    let player_monster = battle_state.player_team[0].0.clone();
 
     // Message for what move was used
-    thread::sleep(Duration::from_millis(100));
+    timeline.pause(0.1);
 
     let f = format!(
         "{} used {}!",
         &player_monster, monsters_map[&player_monster].moves[current_choice].name
     );
-    draw_battle(wincan, &battle_draw, None, Some(f))?;
+    timeline.say(f);
 
-    // Apply the damage internally and to the drawing
-    let d = monster::calculate_damage(battle_draw.monsters, battle_state, current_choice);
-    battle_draw.apply_enemy_damage(d);
-    battle_state.enemy_team[0].1 = battle_draw.enemy_health;
+    // Apply the damage internally, then drain the health bar to match
+    let d = monster::calculate_damage(monsters_map, battle_state, current_choice);
+    let enemy_health = (battle_state.enemy_team[0].1 - d).clamp(0.0, 100.0);
+    battle_state.enemy_team[0].1 = enemy_health;
+    timeline.push(Step::EnemyHealth(enemy_health));
 
     // Check effectiveness, and message based upon it
     let effectiveness = monster::str_effectiveness(
         &monsters_map[&player_monster].moves[current_choice],
         &monsters_map[&enemy_monster].monster_type,
     );
-    match effectiveness {
-        Some(s) => {
-            thread::sleep(Duration::from_millis(300));
-            draw_battle(wincan, &battle_draw, None, Some(s))?;
-        }
-        None => {
-            draw_battle(wincan, &battle_draw, None, None)?;
-        }
+    if let Some(s) = effectiveness {
+        timeline.pause(0.3);
+        timeline.say(s);
     }
 
-    thread::sleep(Duration::from_millis(300));
+    timeline.pause(0.3);
 
-    if battle_draw.enemy_health == 0.0 {
+    if enemy_health == 0.0 {
         // Write message that enemy is KO'd
-        thread::sleep(Duration::from_millis(200));
+        timeline.pause(0.2);
         let f = format!("{} KO'd {}!", &player_monster, &enemy_monster);
-        draw_battle(wincan, &battle_draw, None, Some(f))?;
+        timeline.say(f);
 
-        thread::sleep(Duration::from_millis(200));
+        timeline.pause(0.2);
         let exp = exp_gain(&enemy_monster, &monsters_map);
         let mut f = format!("{} gained {} experience.", &player_monster, exp);
         if battle_state.player_team[0].2 / 10 < (battle_state.player_team[0].2 + exp) / 10 {
             f = format!("{} gained {} experience and leveled up!", &player_monster, exp);
         }
         battle_state.player_team[0].2 += exp;
-        battle_draw.player_level = battle_state.player_team[0].2 / 10;
-        draw_battle(wincan, &battle_draw, None, Some(f))?;
+        timeline.push(Step::PlayerLevel(battle_state.player_team[0].2 / 10));
+        timeline.say(f);
 
         if battle_state.enemy_team.len() > 1 && battle_state.enemy_team[1].1 > 0.0 {
             battle_state.enemy_team = verify_team(&battle_state.enemy_team);
-            battle_draw.enemy_health = battle_state.enemy_team[0].1;
-            battle_draw.enemy_name = battle_state.enemy_team[0].0.clone();
-            battle_draw.opp_level = battle_state.enemy_team[0].2 / 10;
             battle_state.opp_attack_stages = 0;
             battle_state.opp_defense_stages = 0;
             
-            thread::sleep(Duration::from_millis(200));
-            let f = format!("Enemy sent out {}!", battle_state.enemy_team[0].0);
-            draw_battle(wincan, &battle_draw, None, Some(f))?;
-            thread::sleep(Duration::from_millis(200));
+            timeline.pause(0.2);
+            let (name, health, exp) = battle_state.enemy_team[0].clone();
+            let f = format!("Enemy sent out {}!", name);
+            timeline.push(Step::EnemyOut { name, health, level: exp / 10 });
+            timeline.say(f);
+            timeline.pause(0.2);
             battle_state.player_turn = !battle_state.player_turn;
         } else {
-            thread::sleep(Duration::from_millis(200));
-            let f = format!("You defeated the enemy!");
-            draw_battle(wincan, &battle_draw, None, Some(f))?;
+            timeline.pause(0.2);
+            timeline.say(String::from("You defeated the enemy!"));
+            if let Some(f) = victory_message {
+                timeline.pause(0.2);
+                timeline.say(f);
+            }
 
             // Fade out back to the overworld
-            let screen = Rect::new(0, 0, CAM_W, CAM_H);
-            wincan.set_draw_color(Color::RGBA(0, 0, 0, 15));
-            for _i in 0..50 {
-                wincan.fill_rect(screen)?;
-                wincan.present();
-            }
-            if matches!(battle_state.battle_type, monster::BattleType::GymTrainer) {
-                return Ok(Map::Gym)
-            } else {
-                return Ok(Map::Overworld);
-            }
+            timeline.push(Step::FadeOut);
+            return BattleResult::Victory;
         }
     }
     battle_state.player_turn = !battle_state.player_turn;
-    Ok(Map::Battle)
+    BattleResult::Ongoing
 }
 
+/// Works out the opponent's move, queueing up everything that happens on the timeline
 pub fn enemy_battle_turn(
-    wincan: &mut sdl2::render::WindowCanvas,
     battle_state: &mut monster::BattleState,
-    battle_draw: &mut Battle,
     monsters_map: &HashMap<String, monster::Monster>,
     enemy_choice: usize,
-) -> Result<Map, String> {
-    let enemy_monster = battle_draw.enemy_name.clone();
-    let player_monster = battle_draw.player_name.clone();
+    timeline: &mut Timeline,
+) -> BattleResult {
+    let enemy_monster = battle_state.enemy_team[0].0.clone();
+    let player_monster = battle_state.player_team[0].0.clone();
+    battle_state.enemy_turns += 1;
 
     if enemy_choice > 3 {
-        thread::sleep(Duration::from_millis(200));
+        timeline.pause(0.2);
         battle_state.enemy_team.swap(0, enemy_choice-3);
         battle_state.enemy_team = verify_team(&battle_state.enemy_team);
-        let f = format!("Enemy switched in {}", &battle_state.enemy_team[0].0);
-        battle_draw.enemy_health = battle_state.enemy_team[0].1;
-        battle_draw.enemy_name = battle_state.enemy_team[0].0.clone();
-        draw_battle(wincan, &battle_draw, None, Some(f))?;
-        return Ok(Map::Battle)
+        let (name, health, exp) = battle_state.enemy_team[0].clone();
+        let f = format!("Enemy switched in {}", &name);
+        timeline.push(Step::EnemyOut { name, health, level: exp / 10 });
+        timeline.say(f);
+        return BattleResult::Ongoing
     }
 
     // Message for what move was used
-
-    thread::sleep(Duration::from_millis(300));
+    timeline.pause(0.3);
 
     let f = format!(
         "{} used {}!",
-        &enemy_monster, monsters_map[&enemy_monster].moves[enemy_choice].name
+        &enemy_monster, battle_state.enemy_move(monsters_map, enemy_choice).name
     );
-    draw_battle(wincan, &battle_draw, None, Some(f))?;
+    timeline.say(f);
 
-    // Apply the damage internally and to the drawing
-    let d = monster::calculate_damage(battle_draw.monsters, battle_state, enemy_choice);
-    battle_draw.apply_player_damage(d);
-    battle_state.player_team[0].1 = battle_draw.player_health;
+    // Apply the damage internally, then drain the health bar to match
+    let d = monster::calculate_damage(monsters_map, battle_state, enemy_choice);
+    let player_health = (battle_state.player_team[0].1 - d).clamp(0.0, 100.0);
+    battle_state.player_team[0].1 = player_health;
+    timeline.push(Step::PlayerHealth(player_health));
     
     // Check effectiveness, and message based upon it
     let effectiveness = monster::str_effectiveness(
-        &monsters_map[&enemy_monster].moves[enemy_choice],
+        battle_state.enemy_move(monsters_map, enemy_choice),
         &monsters_map[&player_monster].monster_type,
     );
-    match effectiveness {
-        Some(s) => {
-            thread::sleep(Duration::from_millis(300));
-            draw_battle(wincan, &battle_draw, None, Some(s))?;
-        }
-        None => {
-            draw_battle(wincan, &battle_draw, None, None)?;
-        }
+    if let Some(s) = effectiveness {
+        timeline.pause(0.3);
+        timeline.say(s);
     }
 
-    thread::sleep(Duration::from_millis(300));
+    timeline.pause(0.3);
 
-    if battle_draw.player_health == 0.0 {
+    if player_health == 0.0 {
         // Write message that player is KO'd
-        thread::sleep(Duration::from_millis(200));
+        timeline.pause(0.2);
         let f = format!("{} KO'd {}!", &enemy_monster, &player_monster);
-        draw_battle(wincan, &battle_draw, None, Some(f))?;
+        timeline.say(f);
         
         if battle_state.player_team.len() > 1 && battle_state.player_team[1].1 > 0.0 {
             battle_state.player_team = verify_team(&battle_state.player_team);
-            battle_draw.player_health = battle_state.player_team[0].1;
-            battle_draw.player_name = battle_state.player_team[0].0.clone();
-            battle_draw.player_level = battle_state.player_team[0].2 / 10;
             battle_state.self_attack_stages = 0;
             battle_state.self_defense_stages = 0;
             
-            thread::sleep(Duration::from_millis(200));
-            let f = format!("Player sent out {}!", battle_state.player_team[0].0);
-            draw_battle(wincan, &battle_draw, None, Some(f))?;
-            thread::sleep(Duration::from_millis(200));
+            timeline.pause(0.2);
+            let (name, health, exp) = battle_state.player_team[0].clone();
+            let f = format!("Player sent out {}!", name);
+            timeline.push(Step::PlayerOut { name, health, level: exp / 10 });
+            timeline.say(f);
+            timeline.pause(0.2);
             battle_state.player_turn = !battle_state.player_turn;
         } else {
-            thread::sleep(Duration::from_millis(200));
-            let f = format!("You blacked out!");
-            draw_battle(wincan, &battle_draw, None, Some(f))?;
+            timeline.pause(0.2);
+            timeline.say(String::from("You blacked out!"));
 
             // Fade out back to the overworld
-            let screen = Rect::new(0, 0, CAM_W, CAM_H);
-            wincan.set_draw_color(Color::RGBA(0, 0, 0, 15));
-            for _i in 0..50 {
-                wincan.fill_rect(screen)?;
-                wincan.present();
-            }
-
-            for item in battle_state.player_team.iter_mut() {
-                (*item).1 = 100.0;
-            }
-            return Ok(Map::Overworld);
+            timeline.push(Step::FadeOut);
+            return BattleResult::Defeat;
         }
     }
     battle_state.player_turn = !battle_state.player_turn;
-    Ok(Map::Battle)
+    BattleResult::Ongoing
 }
 
 fn menu_health_bars(
@@ -602,6 +695,7 @@ fn menu_health_bars(
 
 pub fn draw_monster_menu(
     wincan: &mut sdl2::render::WindowCanvas,
+    assets: &AssetManager,
     battle_init: &Battle,
     battle_state: &monster::BattleState,
     choice: usize,
@@ -659,16 +753,8 @@ pub fn draw_monster_menu(
     }
 
     // Draw OK button
-    let texture_creator = wincan.texture_creator();
     let f = String::from("OK");
-    let surface = battle_init
-        .font
-        .render(&f)
-        .blended(Color::BLACK)
-        .map_err(|e| e.to_string())?;
-    let texture = texture_creator
-        .create_texture_from_surface(&surface)
-        .map_err(|e| e.to_string())?;
+    let texture = assets.text(&f, Color::BLACK)?;
     let TextureQuery { width, height, .. } = texture.query();
     let text_rect = Rect::new(750, 560, 150, 50);
     let text_rect = center(fit(text_rect, width, height), 150, 50);
@@ -681,22 +767,14 @@ pub fn draw_monster_menu(
     wincan.fill_rect(Rect::new(110, 90, 330, 330))?;
 
     if choice == 6 {
-        let texture = texture_creator.load_texture("images/walk1_32.png")?;
         wincan.copy(
-            &texture,
+            assets.texture("images/walk1_32.png"),
             None,
             Rect::new(100 + s, 80 + s, 350 - 2 * s as u32, 350 - 2 * s as u32),
         )?;
 
         let f = String::from("Player");
-        let surface = battle_init
-            .font
-            .render(&f)
-            .blended(Color::RGB(0xbd, 0xcd, 0xde))
-            .map_err(|e| e.to_string())?;
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        let texture = assets.text(&f, Color::RGB(0xbd, 0xcd, 0xde))?;
         let TextureQuery { width, height, .. } = texture.query();
         let text_rect = Rect::new(110, 417, 330, 50);
         let text_rect = center(fit(text_rect, width, height), 330, 50);
@@ -704,14 +782,7 @@ pub fn draw_monster_menu(
 
         // Add stats
         let f = format!("Badges: {}", battle_state.player_badges);
-        let surface = battle_init
-            .font
-            .render(&f)
-            .blended(Color::RGB(0xbd, 0xcd, 0xde))
-            .map_err(|e| e.to_string())?;
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        let texture = assets.text(&f, Color::RGB(0xbd, 0xcd, 0xde))?;
         let TextureQuery { width, height, .. } = texture.query();
         let text_rect = Rect::new(110, 470, 330, 35);
         let text_rect = center(fit(text_rect, width, height), 330, 35);
@@ -719,18 +790,11 @@ pub fn draw_monster_menu(
     } else {
         // Draw focused monster image
         wincan.copy(
-            &battle_init.monster_text_map[&player_team[choice].0],
+            assets.texture(&monster_image(&player_team[choice].0)),
             None,
             Rect::new(100 + s, 80 + s, 350 - 2 * s as u32, 350 - 2 * s as u32),
         )?;
-        let surface = battle_init
-            .font
-            .render(&player_team[choice].0)
-            .blended(Color::RGB(0xbd, 0xcd, 0xde))
-            .map_err(|e| e.to_string())?;
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        let texture = assets.text(&player_team[choice].0, Color::RGB(0xbd, 0xcd, 0xde))?;
         let TextureQuery { width, height, .. } = texture.query();
         let text_rect = Rect::new(110, 417, 330, 50);
         let text_rect = center(fit(text_rect, width, height), 330, 50);
@@ -742,14 +806,7 @@ pub fn draw_monster_menu(
             &battle_init.monsters[&player_team[choice].0].attack_stat,
             &battle_init.monsters[&player_team[choice].0].defense_stat
         );
-        let surface = battle_init
-            .font
-            .render(&f)
-            .blended(Color::RGB(0xbd, 0xcd, 0xde))
-            .map_err(|e| e.to_string())?;
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
+        let texture = assets.text(&f, Color::RGB(0xbd, 0xcd, 0xde))?;
         let TextureQuery { width, height, .. } = texture.query();
         let text_rect = Rect::new(110, 470, 330, 35);
         let text_rect = center(fit(text_rect, width, height), 330, 35);
@@ -798,14 +855,7 @@ pub fn draw_monster_menu(
                 )?;
             } else {
                 let f = String::from("FAINTED");
-                let surface = battle_init
-                    .font
-                    .render(&f)
-                    .blended(Color::WHITE)
-                    .map_err(|e| e.to_string())?;
-                let texture = texture_creator
-                    .create_texture_from_surface(&surface)
-                    .map_err(|e| e.to_string())?;
+                let texture = assets.text(&f, Color::WHITE)?;
                 let TextureQuery { width, height, .. } = texture.query();
                 let text_rect = Rect::new(item.x + 10, item.y + 105 - 10 - 25, 280, 25);
                 let text_rect = center(fit(text_rect, width, height), 280, 25);
@@ -817,7 +867,6 @@ pub fn draw_monster_menu(
         }
     }
 
-    wincan.present();
     Ok(())
 }
 
@@ -862,4 +911,208 @@ pub fn exp_gain(monster: &String, monsters: &HashMap<String, monster::Monster>)
     } else {
         5
     };
-}
\ No newline at end of file
+}
+
+/// The money won for beating an opponent: more for tougher kinds of opponents, and for higher levels
+pub fn prize_money(battle_type: &monster::BattleType, level: usize) -> u32 {
+    let base = match battle_type {
+        monster::BattleType::Wild => 0,
+        monster::BattleType::Trainer => 40,
+        monster::BattleType::GymTrainer => 60,
+        monster::BattleType::GymLeader => 300,
+        monster::BattleType::Champion => 1000,
+    };
+    base * (level as u32 + 1)
+}
+
+/// The battle screen; the battle itself is set up by `Game::start_battle`
+pub struct BattleScene {
+    current_choice: usize,
+    chosen: Option<usize>,
+    timeline: Timeline,
+    /// How the last turn ended, acted on once the timeline has finished showing it
+    outcome: BattleResult,
+}
+
+impl BattleScene {
+    pub fn new() -> BattleScene {
+        BattleScene {
+            current_choice: 0,
+            chosen: None,
+            timeline: Timeline::new(),
+            outcome: BattleResult::Ongoing,
+        }
+    }
+}
+
+impl Default for BattleScene {
+    fn default() -> Self {
+        BattleScene::new()
+    }
+}
+
+impl<'a> Scene<'a> for BattleScene {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        // While a turn is being shown, Confirm only hurries it along
+        if !self.timeline.is_empty() {
+            if input.was_pressed(Action::Confirm) {
+                self.timeline.skip(&mut game.battle_draw);
+            }
+            return Ok(Transition::None);
+        }
+
+        if input.was_repeated(Action::Left) {
+            self.current_choice = (self.current_choice + 3) % 4;
+        }
+        if input.was_repeated(Action::Right) {
+            self.current_choice = (self.current_choice + 1) % 4;
+        }
+        if input.was_pressed(Action::Menu) || input.was_pressed(Action::Down) {
+            return Ok(Transition::Push(Box::new(menu::TeamMenuScene::new())));
+        }
+        if input.was_pressed(Action::Confirm) {
+            self.chosen = Some(self.current_choice);
+        }
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String> {
+        game.battle_draw.player_sprite.advance(dt);
+        game.battle_draw.enemy_sprite.advance(dt);
+        self.timeline.advance(&mut game.battle_draw, dt);
+        if !self.timeline.is_empty() {
+            return Ok(Transition::None);
+        }
+        match self.outcome {
+            BattleResult::Victory => return battle_won(game),
+            BattleResult::Defeat => return blacked_out(game),
+            BattleResult::Ongoing => {}
+        }
+
+        // A new lead once the team menu closes means the player switched monsters, using up their turn
+        let (name, health, exp) = game.battle_state.player_team[0].clone();
+        if health > 0.0 && name != game.battle_draw.player_name {
+            game.battle_state.self_attack_stages = 0;
+            game.battle_state.self_defense_stages = 0;
+            let f = format!("You switched in {}!", name);
+            self.timeline.push(Step::PlayerOut { name, health, level: exp / 10 });
+            self.timeline.say(f);
+            self.outcome = enemy_turn(game, &mut self.timeline);
+            return Ok(Transition::None);
+        }
+
+        let choice = match self.chosen.take() {
+            Some(choice) => choice,
+            None => return Ok(Transition::None),
+        };
+
+        game.battle_state.player_turn = true;
+        self.outcome = player_battle_turn(
+            &mut game.battle_state,
+            game.monsters,
+            choice,
+            game.battle_draw.victory_message.clone(),
+            &mut self.timeline,
+        );
+        if self.outcome == BattleResult::Ongoing && !game.battle_state.player_turn {
+            self.outcome = enemy_turn(game, &mut self.timeline);
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut sdl2::render::WindowCanvas, _alpha: f64) -> Result<(), String> {
+        // Moves can only be picked once the last turn has been shown
+        let choice = if self.timeline.is_empty() { Some(self.current_choice) } else { None };
+        draw_battle(wincan, &game.assets, &game.battle_draw, choice, self.timeline.message().as_deref())?;
+        if let Some(progress) = self.timeline.fade() {
+            ui::draw_fade(wincan, progress)?;
+        }
+        Ok(())
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Battle(self.current_choice)
+    }
+}
+
+/// Lets the opponent take its turn
+fn enemy_turn(game: &mut Game, timeline: &mut Timeline) -> BattleResult {
+    let enemy_choice = ai::ai_agent(game.difficulty, &game.enemy_profile, game.monsters, &mut game.battle_state);
+    enemy_battle_turn(&mut game.battle_state, game.monsters, enemy_choice, timeline)
+}
+
+/// Sends the player back to where they last healed, with their team healed and half their money lost
+fn blacked_out<'a>(game: &mut Game<'a>) -> Result<Transition<'a>, String> {
+    game.trainer = None;
+    game.heal_team();
+    game.last_battle = Instant::now();
+    let lost = game.money / 2;
+    game.money -= lost;
+    game.save()?;
+
+    let text = format!("Your team was beaten! You dropped {} on the way back.", shop::describe_money(lost));
+    Ok(Transition::Reset(vec![
+        Box::new(overworld::OverworldScene::at_spawn(game, &game.respawn)),
+        Box::new(DialogueScene::message(game, "", &text)?),
+    ]))
+}
+
+/// Hands out the rewards of the battle and leaves it
+fn battle_won<'a>(game: &mut Game<'a>) -> Result<Transition<'a>, String> {
+    // Set last_battle to now so the player isn't immediately sent into another battle
+    game.last_battle = Instant::now();
+
+    if let Some(trainer) = game.trainer.take() {
+        game.defeated_trainers.insert(trainer);
+    }
+
+    // Trainers, leaders and the champion hand over prize money
+    let prize = prize_money(game.battle_state.battle_type, game.battle_draw.opp_level);
+    game.money += prize;
+    let prize_message = format!("You got {} for winning!", shop::describe_money(prize));
+
+    match game.battle_state.battle_type {
+        monster::BattleType::GymLeader => {
+            game.player_badges.insert(game.gym_no as u32);
+            game.battle_state.player_badges = game.player_badges.len();
+
+            // Every monster on the team shares in the gym's reward
+            let reward = game.gyms[game.gym_no].reward;
+            for item in game.battle_state.player_team.iter_mut() {
+                item.2 += reward;
+            }
+            game.battle_state.player_team = verify_team(&game.battle_state.player_team);
+            let gym_no = game.gym_no;
+            gym::reload_maze(game, gym_no);
+            game.save()?;
+
+            // Spawn the player at their house
+            Ok(Transition::Reset(vec![
+                Box::new(overworld::OverworldScene::at_spawn(game, "home")),
+                Box::new(DialogueScene::message(game, "", &prize_message)?),
+            ]))
+        }
+        monster::BattleType::Champion => {
+            // Roll the credits, then continue from the player's house
+            game.save()?;
+            Ok(Transition::Reset(vec![
+                Box::new(overworld::OverworldScene::at_spawn(game, "home")),
+                Box::new(DialogueScene::message(game, "", &prize_message)?),
+                Box::new(credits::CreditsScene::new()),
+            ]))
+        }
+        monster::BattleType::Wild => {
+            // Wild monsters beaten count towards quests
+            let monsters = game.monsters;
+            if let Some(wild) = monsters.get(&game.battle_state.enemy_team[0].0) {
+                game.quest_log.beat_wild(&wild.monster_type);
+            }
+            game.save()?;
+            Ok(Transition::Pop)
+        }
+        _ => {
+            game.save()?;
+            Ok(Transition::Replace(Box::new(DialogueScene::message(game, "", &prize_message)?)))
+        }
+    }
+}
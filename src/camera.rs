@@ -0,0 +1,45 @@
+use sdl2::rect::Rect;
+
+pub const CAM_W: u32 = 1280;
+pub const CAM_H: u32 = 720;
+
+/// The part of a world that is on screen
+///
+/// The camera keeps what it follows in the middle of the screen, except near the edges of
+/// the world, where it stops so that nothing past them is shown. Worlds smaller than the
+/// screen are drawn from the top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    view: Rect,
+}
+
+impl Camera {
+    /// A camera centered on `target`, kept within a world of the given size
+    pub fn following(target: Rect, world_w: u32, world_h: u32) -> Camera {
+        let x = target.center().x() - (CAM_W / 2) as i32;
+        let y = target.center().y() - (CAM_H / 2) as i32;
+        Camera {
+            view: Rect::new(
+                x.clamp(0, world_w.saturating_sub(CAM_W) as i32),
+                y.clamp(0, world_h.saturating_sub(CAM_H) as i32),
+                CAM_W,
+                CAM_H,
+            ),
+        }
+    }
+
+    /// The part of the world on screen, in world coordinates
+    pub fn view(&self) -> Rect {
+        self.view
+    }
+
+    /// Where something in the world is drawn on screen
+    pub fn to_screen(&self, rect: Rect) -> Rect {
+        Rect::new(rect.x() - self.view.x(), rect.y() - self.view.y(), rect.width(), rect.height())
+    }
+
+    /// Whether any part of `rect` is on screen
+    pub fn sees(&self, rect: &Rect) -> bool {
+        self.view.has_intersection(*rect)
+    }
+}
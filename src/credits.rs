@@ -0,0 +1,112 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureQuery, WindowCanvas};
+
+use std::time::Instant;
+
+use crate::assets::AssetManager;
+use crate::camera::{CAM_W, CAM_H};
+use crate::input::Input;
+use crate::scene::{Game, Scene, SceneKind, Transition};
+
+// How long each slide is shown, in seconds
+const SLIDE_TIME: f64 = 5.0;
+
+const TEAM: &[&str; 6] = &["Title", "Adam", "Azeez", "Burhan", "Gurmail", "Zhiyi"];
+const BGS: &[Color; 6] = &[	Color::BLACK,
+							Color::RGB(0x91, 0xD5, 0xFF), // Adam
+							Color::MAGENTA, // Azeez
+							Color::BLUE, // Burhan
+							Color::YELLOW, // Gurmail
+							Color::RED ]; // Zhiyi
+
+/// The pictures of the team, which are shown when they are there
+pub fn image_paths() -> Vec<String> {
+	TEAM.iter().map(|member| format!("images/{}.png", member)).collect()
+}
+
+/// Whether every slide has been shown after `elapsed` seconds
+pub fn credits_finished(elapsed: f64) -> bool {
+	(elapsed / SLIDE_TIME) as usize >= TEAM.len()
+}
+
+/// Draws the credits slide for the time since the credits started
+///
+/// * `elapsed` - Seconds since the credits started
+pub fn draw_credits(
+	wincan: &mut WindowCanvas,
+	assets: &AssetManager,
+	elapsed: f64,
+) -> Result<(), String> {
+	let i = ((elapsed / SLIDE_TIME) as usize).min(TEAM.len() - 1);
+	let member = TEAM[i];
+
+	// Set the background color specified by each member, and clear the previous image
+	wincan.set_draw_color(BGS[i]);
+	wincan.clear();
+
+	// Use the image with their name
+	let image_path = format!("images/{}.png", member);
+	match assets.find(&image_path) {
+		Some(id) => {
+			let monster_image = assets.get(id);
+			// Get the image dimensions for use in centering it in the display window
+			let w = monster_image.query().width;
+			let h = monster_image.query().height;
+
+			// Have the image take up the entire height, and be centered horizontally
+			let width = (CAM_H * w / h).min(CAM_W);
+			let rect = Rect::new(((CAM_W - width) / 2) as i32, 0, width, CAM_H);
+			wincan.copy(monster_image, None, rect)?;
+		}
+		None => {
+			// Fall back to just their name when there is no image
+			let texture = assets.text(member, Color::WHITE)?;
+			let TextureQuery { width, height, .. } = texture.query();
+			let width = width * 120 / height;
+			let rect = Rect::new(((CAM_W - width.min(CAM_W)) / 2) as i32, (CAM_H / 2 - 60) as i32, width.min(CAM_W), 120);
+			wincan.copy(&texture, None, rect)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Rolls the credits, then goes back to the scene below
+pub struct CreditsScene {
+	start: Instant,
+}
+
+impl CreditsScene {
+	pub fn new() -> CreditsScene {
+		CreditsScene { start: Instant::now() }
+	}
+}
+
+impl Default for CreditsScene {
+	fn default() -> Self {
+		CreditsScene::new()
+	}
+}
+
+impl<'a> Scene<'a> for CreditsScene {
+	fn handle_input(&mut self, _game: &mut Game<'a>, _input: &Input) -> Result<Transition<'a>, String> {
+		Ok(Transition::None)
+	}
+
+	fn update(&mut self, _game: &mut Game<'a>, _dt: f64) -> Result<Transition<'a>, String> {
+		if credits_finished(self.start.elapsed().as_secs_f64()) {
+			Ok(Transition::Pop)
+		} else {
+			Ok(Transition::None)
+		}
+	}
+
+	fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+		draw_credits(wincan, &game.assets, self.start.elapsed().as_secs_f64())
+	}
+
+	fn kind(&self) -> SceneKind {
+		SceneKind::Credits
+	}
+}
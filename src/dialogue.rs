@@ -0,0 +1,378 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureQuery, WindowCanvas};
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::assets::AssetManager;
+use crate::input::{Action, Input};
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::ui;
+use crate::world::World;
+
+pub const DIALOGUE_FILE: &str = "./data/dialogue.txt";
+
+/// The option that agrees to a yes or no question the game asks, like whether to go into a gym
+pub const YES: &str = "Yes";
+
+// Scripts the game plays by name, which the dialogue file has to have
+const REQUIRED_SCRIPTS: [&str; 8] = [
+    "trainer",
+    "trainer_defeated",
+    "leader",
+    "enter_gym",
+    "gym_locked",
+    "enter_champion",
+    "champion_locked",
+    "leave_gym",
+];
+
+// Characters typed out per second
+const TYPING_SPEED: f64 = 60.0;
+
+// The text box along the bottom of the screen, and the lines of text in it
+const BOX: (i32, i32, u32, u32) = (140, 520, 1000, 170);
+const LINE_HEIGHT: u32 = 36;
+const LINES_PER_BOX: usize = 3;
+
+/// One page of a script: who is speaking, what they say, and the options to choose from, if any
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub speaker: String,
+    pub text: String,
+    pub options: Vec<String>,
+}
+
+/// Every dialogue script, loaded from `data/dialogue.txt`
+///
+/// The file has one record per line after a header line; the records of a script are its
+/// pages, in order:
+///
+/// * `say,script,speaker,text` - A page of text; the speaker can be left empty for signs and
+///   narration, and the text can have commas
+/// * `choose,script,speaker,options,question` - Asks a question, with options like `Yes;No`;
+///   this ends the script with the option chosen
+///
+/// Text can name values given when the script is played, like `{badge}`.
+pub struct Dialogues {
+    scripts: HashMap<String, Vec<Page>>,
+}
+
+impl Dialogues {
+    /// Loads every script, making sure every sign of the world has one to show
+    pub fn load(path: &str, world: &World) -> Result<Dialogues, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        let dialogues = Dialogues::parse(&contents).map_err(|e| format!("{}: {}", path, e))?;
+
+        for (area, map) in world.areas() {
+            if let Some(sign) = map.signs.iter().find(|s| !dialogues.scripts.contains_key(&s.script)) {
+                return Err(format!("{}: there is no {} script for the sign in {}", path, sign.script, area));
+            }
+        }
+
+        Ok(dialogues)
+    }
+
+    fn parse(contents: &str) -> Result<Dialogues, String> {
+        let mut scripts: HashMap<String, Vec<Page>> = HashMap::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let kind = line.trim().split(',').next().unwrap_or("");
+            let v = match kind {
+                "say" => line.trim().splitn(4, ',').collect::<Vec<&str>>(),
+                "choose" => line.trim().splitn(5, ',').collect::<Vec<&str>>(),
+                _ => vec![kind],
+            };
+            let page = match v[..] {
+                [""] => continue,
+                ["say", _, speaker, text] => Page {
+                    speaker: speaker.to_string(),
+                    text: text.to_string(),
+                    options: Vec::new(),
+                },
+                ["choose", _, speaker, options, question] => Page {
+                    speaker: speaker.to_string(),
+                    text: question.to_string(),
+                    options: options.split(';').map(String::from).collect(),
+                },
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            };
+
+            let script = scripts.entry(v[1].to_string()).or_default();
+            if script.last().is_some_and(|p| !p.options.is_empty()) {
+                return Err(format!("line {}: {} already ended with a question", line_number, v[1]));
+            }
+            script.push(page);
+        }
+
+        if let Some(name) = REQUIRED_SCRIPTS.iter().find(|s| !scripts.contains_key(**s)) {
+            return Err(format!("there is no {} script", name));
+        }
+
+        Ok(Dialogues { scripts })
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.scripts.contains_key(name)
+    }
+
+    /// The pages of the script with the given name, or of `fallback` if there is none
+    pub fn script<'d>(&'d self, name: &str, fallback: &str) -> &'d [Page] {
+        match self.scripts.get(name).or_else(|| self.scripts.get(fallback)) {
+            Some(pages) => pages,
+            None => panic!("There is no {} script", fallback),
+        }
+    }
+}
+
+/// What fits in the text box at once: the speaker, up to a few lines, and the options of a question
+struct Screen {
+    speaker: String,
+    lines: Vec<String>,
+    options: Vec<String>,
+}
+
+/// A script being played: typing out each screen of text, then waiting for the player
+pub struct Dialogue {
+    screens: Vec<Screen>,
+    screen: usize,
+    /// How many characters of the screen have been typed out
+    typed: f64,
+    choice: usize,
+}
+
+impl Dialogue {
+    /// Starts a script, filling in any `{name}` in it from `values` and splitting its pages into screens
+    pub fn new(assets: &AssetManager, pages: &[Page], values: &[(&str, &str)]) -> Result<Dialogue, String> {
+        let mut screens = Vec::new();
+        for page in pages {
+            let mut text = page.text.clone();
+            for (name, value) in values {
+                text = text.replace(&format!("{{{}}}", name), value);
+            }
+
+            // Long pages go on over more than one screen; a question waits until the last
+            let mut lines = ui::wrap_text(assets, &text, BOX.2 - 60, LINE_HEIGHT)?;
+            if lines.is_empty() {
+                lines.push(String::new());
+            }
+            let chunks: Vec<&[String]> = lines.chunks(LINES_PER_BOX).collect();
+            for (index, chunk) in chunks.iter().enumerate() {
+                let last = index + 1 == chunks.len();
+                screens.push(Screen {
+                    speaker: page.speaker.clone(),
+                    lines: chunk.to_vec(),
+                    options: if last { page.options.clone() } else { Vec::new() },
+                });
+            }
+        }
+
+        Ok(Dialogue {
+            screens,
+            screen: 0,
+            typed: 0.0,
+            choice: 0,
+        })
+    }
+
+    fn length(screen: &Screen) -> usize {
+        screen.lines.iter().map(|l| l.chars().count()).sum()
+    }
+
+    fn finished_typing(&self) -> bool {
+        self.typed as usize >= Dialogue::length(&self.screens[self.screen])
+    }
+
+    /// Reacts to the input of this frame; returns `Some` once the script is over, along with
+    /// the option chosen if it ended with a question
+    pub fn handle_input(&mut self, input: &Input) -> Option<Option<String>> {
+        if self.screens.is_empty() {
+            return Some(None);
+        }
+        let options = &self.screens[self.screen].options;
+
+        // Confirm hurries the typing along before moving on
+        if !self.finished_typing() {
+            if input.was_pressed(Action::Confirm) || input.was_pressed(Action::Cancel) {
+                self.typed = Dialogue::length(&self.screens[self.screen]) as f64;
+            }
+            return None;
+        }
+
+        if !options.is_empty() {
+            if input.was_repeated(Action::Up) {
+                self.choice = (self.choice + options.len() - 1) % options.len();
+            }
+            if input.was_repeated(Action::Down) {
+                self.choice = (self.choice + 1) % options.len();
+            }
+            // Cancelling picks the last option, like "No"
+            if input.was_pressed(Action::Cancel) {
+                return Some(options.last().cloned());
+            }
+            if input.was_pressed(Action::Confirm) {
+                return Some(Some(options[self.choice].clone()));
+            }
+            return None;
+        }
+
+        if input.was_pressed(Action::Confirm) || input.was_pressed(Action::Cancel) {
+            if self.screen + 1 == self.screens.len() {
+                return Some(None);
+            }
+            self.screen += 1;
+            self.typed = 0.0;
+        }
+        None
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.typed += TYPING_SPEED * dt;
+    }
+
+    pub fn draw(&self, wincan: &mut WindowCanvas, assets: &AssetManager) -> Result<(), String> {
+        let screen = match self.screens.get(self.screen) {
+            Some(screen) => screen,
+            None => return Ok(()),
+        };
+
+        let text_box = Rect::new(BOX.0, BOX.1, BOX.2, BOX.3);
+        if !screen.speaker.is_empty() {
+            let tab = Rect::new(BOX.0, BOX.1 - 44, 240, 50);
+            wincan.set_draw_color(Color::WHITE);
+            wincan.fill_rect(tab)?;
+            wincan.set_draw_color(Color::RGB(0x20, 0x41, 0x6a));
+            wincan.fill_rect(Rect::new(tab.x() + 5, tab.y() + 5, tab.width() - 10, tab.height() - 10))?;
+            ui::draw_text(wincan, assets, &screen.speaker, Color::WHITE, Rect::new(tab.x() + 15, tab.y() + 9, tab.width() - 30, 32))?;
+        }
+        wincan.set_draw_color(Color::WHITE);
+        wincan.fill_rect(text_box)?;
+        wincan.set_draw_color(Color::BLACK);
+        wincan.fill_rect(Rect::new(BOX.0 + 5, BOX.1 + 5, BOX.2 - 10, BOX.3 - 10))?;
+
+        // Each line is drawn whole and cut off where the typing has got to, so that typing
+        // out a line does not draw its text again for every character
+        let mut left = self.typed as usize;
+        for (number, line) in screen.lines.iter().enumerate() {
+            if left == 0 {
+                break;
+            }
+            let count = line.chars().count();
+            let typed: String = line.chars().take(left).collect();
+            left = left.saturating_sub(count);
+
+            let texture = assets.text(line, Color::WHITE)?;
+            let TextureQuery { width, height, .. } = texture.query();
+            let (typed_width, _) = assets.font().size_of(&typed).map_err(|e| e.to_string())?;
+            let typed_width = typed_width.min(width);
+            let scale = LINE_HEIGHT as f32 / height as f32;
+            let src = Rect::new(0, 0, typed_width.max(1), height);
+            let dest = Rect::new(
+                BOX.0 + 30,
+                BOX.1 + 25 + number as i32 * (LINE_HEIGHT as i32 + 8),
+                ((typed_width as f32 * scale) as u32).max(1),
+                LINE_HEIGHT,
+            );
+            wincan.copy(&texture, src, dest)?;
+        }
+
+        // The options of a question show up once it is typed out
+        if !screen.options.is_empty() && self.finished_typing() {
+            let height = 20 + 44 * screen.options.len() as u32;
+            let menu = Rect::new(BOX.0 + BOX.2 as i32 - 260, BOX.1 - height as i32 - 10, 260, height);
+            wincan.set_draw_color(Color::WHITE);
+            wincan.fill_rect(menu)?;
+            wincan.set_draw_color(Color::BLACK);
+            wincan.fill_rect(Rect::new(menu.x() + 5, menu.y() + 5, menu.width() - 10, menu.height() - 10))?;
+            for (index, option) in screen.options.iter().enumerate() {
+                let y = menu.y() + 12 + 44 * index as i32;
+                if index == self.choice {
+                    wincan.set_draw_color(Color::RGB(0xf6, 0x52, 0x41));
+                    wincan.fill_rect(Rect::new(menu.x() + 10, y, menu.width() - 20, 40))?;
+                }
+                ui::draw_text(wincan, assets, option, Color::WHITE, Rect::new(menu.x() + 20, y + 4, menu.width() - 40, 32))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What happens once a script is over, given the option chosen if it ended with a question
+pub type OnEnd<'a> = Box<dyn FnOnce(&mut Game<'a>, Option<&str>) -> Transition<'a> + 'a>;
+
+/// Plays a script over the scene below, then goes back to it or does whatever comes next
+pub struct DialogueScene<'a> {
+    dialogue: Dialogue,
+    on_end: Option<OnEnd<'a>>,
+}
+
+impl<'a> DialogueScene<'a> {
+    /// Plays the script with the given name, or `fallback` if there is none
+    pub fn new(game: &Game<'a>, script: &str, fallback: &str, values: &[(&str, &str)]) -> Result<DialogueScene<'a>, String> {
+        let pages = game.dialogues.script(script, fallback);
+        Ok(DialogueScene {
+            dialogue: Dialogue::new(&game.assets, pages, values)?,
+            on_end: None,
+        })
+    }
+
+    /// Shows a single page of text that is not in the dialogue file, like a notice
+    pub fn message(game: &Game<'a>, speaker: &str, text: &str) -> Result<DialogueScene<'a>, String> {
+        let page = Page {
+            speaker: speaker.to_string(),
+            text: text.to_string(),
+            options: Vec::new(),
+        };
+        Ok(DialogueScene {
+            dialogue: Dialogue::new(&game.assets, &[page], &[])?,
+            on_end: None,
+        })
+    }
+
+    /// Decides what happens once the script is over; without this, the scene below comes back
+    ///
+    /// Scenes pushed by `on_end` take the place of the dialogue.
+    pub fn then(mut self, on_end: impl FnOnce(&mut Game<'a>, Option<&str>) -> Transition<'a> + 'a) -> DialogueScene<'a> {
+        self.on_end = Some(Box::new(on_end));
+        self
+    }
+}
+
+impl<'a> Scene<'a> for DialogueScene<'a> {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        let answer = match self.dialogue.handle_input(input) {
+            Some(answer) => answer,
+            None => return Ok(Transition::None),
+        };
+        let transition = match self.on_end.take() {
+            Some(on_end) => on_end(game, answer.as_deref()),
+            None => Transition::Pop,
+        };
+        Ok(match transition {
+            Transition::Push(scene) => Transition::Replace(scene),
+            Transition::None => Transition::Pop,
+            other => other,
+        })
+    }
+
+    fn update(&mut self, _game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String> {
+        self.dialogue.update(dt);
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        self.dialogue.draw(wincan, &game.assets)
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Dialogue
+    }
+}
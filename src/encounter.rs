@@ -0,0 +1,129 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::monster::Monster;
+use crate::parse::parse_number;
+use crate::world::World;
+
+pub const ENCOUNTER_FILE: &str = "./data/encounters.txt";
+
+/// A monster that can be met in a zone, how often compared to the others, and at which levels
+pub struct Species {
+    pub name: String,
+    pub weight: u32,
+    pub min_level: usize,
+    pub max_level: usize,
+}
+
+/// The wild monsters of one kind of place in one area, like the grass of a route
+pub struct EncounterTable {
+    /// The chance of meeting a monster with each step, from 0 to 1
+    pub rate: f64,
+    /// The type every monster of the zone has, if the zone calls for one
+    pub monster_type: Option<String>,
+    pub species: Vec<Species>,
+}
+
+impl EncounterTable {
+    /// Whether a wild monster jumps out after a step
+    pub fn roll_step<R: Rng>(&self, rng: &mut R) -> bool {
+        rng.gen_bool(self.rate)
+    }
+
+    /// Picks a monster from the table by weight, at full health and a level in its range
+    pub fn choose<R: Rng>(&self, rng: &mut R) -> (String, f32, usize) {
+        let species = self.species.choose_weighted(rng, |s| s.weight).unwrap();
+        let level = rng.gen_range(species.min_level..=species.max_level);
+        // Levels are a tenth of a monster's experience
+        (species.name.clone(), 100.0, level * 10)
+    }
+}
+
+/// The wild monsters of every zone of every area
+///
+/// `data/encounters.txt` has one record per line after a header line:
+///
+/// * `zone,area,zone,rate` or `zone,area,zone,rate,type` - A zone of an area, with the percent chance
+///   of an encounter on each step, and the type all of its monsters must have
+/// * `species,area,zone,monster,weight,min level,max level` - A monster of a zone
+pub struct Encounters {
+    tables: HashMap<(String, String), EncounterTable>,
+}
+
+impl Encounters {
+    /// Loads the encounter tables, making sure every zone of the world has monsters that exist
+    pub fn load(path: &str, monsters: &HashMap<String, Monster>, world: &World) -> Result<Encounters, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        let encounters = Encounters::parse(&contents, monsters).map_err(|e| format!("{}: {}", path, e))?;
+
+        for (area, map) in world.areas() {
+            for zone in map.zones() {
+                if encounters.table(area, zone).is_none() {
+                    return Err(format!("{}: no monsters for the {} of {}", path, zone, area));
+                }
+            }
+        }
+
+        Ok(encounters)
+    }
+
+    fn parse(contents: &str, monsters: &HashMap<String, Monster>) -> Result<Encounters, String> {
+        let mut tables: HashMap<(String, String), EncounterTable> = HashMap::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            match v[0] {
+                "" => {}
+                "zone" if v.len() == 4 || v.len() == 5 => {
+                    let rate: f64 = parse_number(v[3], line_number)?;
+                    if !(0.0..=100.0).contains(&rate) {
+                        return Err(format!("line {}: the rate is a percent, from 0 to 100", line_number));
+                    }
+                    tables.insert((v[1].to_string(), v[2].to_string()), EncounterTable {
+                        rate: rate / 100.0,
+                        monster_type: v.get(4).map(|t| t.to_string()),
+                        species: Vec::new(),
+                    });
+                }
+                "species" if v.len() == 7 => {
+                    let table = tables
+                        .get_mut(&(v[1].to_string(), v[2].to_string()))
+                        .ok_or(format!("line {}: the {} of {} has no zone record before it", line_number, v[2], v[1]))?;
+                    let monster = monsters
+                        .get(v[3])
+                        .ok_or(format!("line {}: unknown monster {}", line_number, v[3]))?;
+                    if let Some(monster_type) = table.monster_type.as_ref().filter(|t| **t != monster.monster_type) {
+                        return Err(format!("line {}: {} is not a {} type", line_number, v[3], monster_type));
+                    }
+                    let species = Species {
+                        name: v[3].to_string(),
+                        weight: parse_number(v[4], line_number)?,
+                        min_level: parse_number(v[5], line_number)?,
+                        max_level: parse_number(v[6], line_number)?,
+                    };
+                    if species.weight == 0 || species.min_level > species.max_level {
+                        return Err(format!("line {}: expected a weight above 0 and min level up to max level", line_number));
+                    }
+                    table.species.push(species);
+                }
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        if let Some(((area, zone), _)) = tables.iter().find(|(_, t)| t.species.is_empty()) {
+            return Err(format!("the {} of {} has no monsters", zone, area));
+        }
+
+        Ok(Encounters { tables })
+    }
+
+    /// The wild monsters of a zone of an area
+    pub fn table(&self, area: &str, zone: &str) -> Option<&EncounterTable> {
+        self.tables.get(&(area.to_string(), zone.to_string()))
+    }
+}
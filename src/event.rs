@@ -0,0 +1,275 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::dialogue::{self, DialogueScene, Dialogues};
+use crate::item::Items;
+use crate::npc::Npc;
+use crate::overworld;
+use crate::parse::parse_number;
+use crate::scene::{Game, Transition};
+use crate::shop::ShopScene;
+use crate::world::World;
+
+pub const EVENT_FILE: &str = "./data/events.txt";
+
+/// One command of an event script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Plays a dialogue script, waiting until it is over
+    Say(String),
+    /// Plays a dialogue script ending in a choice, then sets the flag if the player answered
+    /// yes and clears it otherwise
+    Ask(String, String),
+    /// Walks a trainer of the area to a point, waiting until they get there
+    Move(String, i32, i32),
+    /// Gives the player some of an item
+    Give(String, u32),
+    /// Heals the player's team, waiting until the healing is over
+    Heal,
+    /// Makes the spawn point with the given name where the player wakes up after blacking out
+    Respawn(String),
+    /// Opens the shop, waiting until the player leaves it
+    Shop,
+    /// Battles a trainer of the area, going on once the player wins
+    Battle(String),
+    Set(String),
+    Clear(String),
+    /// Runs the commands up to the matching `End` only if the flag is set, or only if it is
+    /// not when this is false
+    If(String, bool),
+    End,
+    /// Ends the script early
+    Stop,
+}
+
+/// Every event script, loaded from `data/events.txt`
+///
+/// The file has one record per line after a header line. `event,name` starts a script, and
+/// the commands after it, up to the next `event`, are run in order:
+///
+/// * `say,dialogue` - Plays a script from `data/dialogue.txt`
+/// * `ask,dialogue,flag` - Plays a script ending in a yes or no choice, setting the flag if the
+///   player answers yes and clearing it otherwise
+/// * `move,npc,x,y` - Walks a trainer of the area to a point
+/// * `give,item,count` - Items are listed in `data/items.txt`
+/// * `heal` - Heals the player's team
+/// * `respawn,spawn` - Makes the spawn point where the player wakes up after blacking out
+/// * `shop` - Opens the shop
+/// * `battle,npc` - Battles a trainer of the area; the script goes on if the player wins
+/// * `set,flag` and `clear,flag` - Flags are saved along with the rest of the player's progress
+/// * `if,flag` or `if,!flag` - Runs the commands up to the matching `end` only if the flag is
+///   set, or not set
+/// * `end` and `stop` - `stop` ends the script early
+///
+/// Maps start events when the player walks into a spot or talks to a trainer.
+pub struct Events {
+    scripts: HashMap<String, Vec<Command>>,
+}
+
+impl Events {
+    /// Loads every event, making sure everything they and the maps name exists
+    pub fn load(path: &str, world: &World, dialogues: &Dialogues, items: &Items) -> Result<Events, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        let events = Events::parse(&contents).map_err(|e| format!("{}: {}", path, e))?;
+
+        let npcs: HashSet<&str> = world.areas().flat_map(|(_, map)| map.npcs.iter().map(|n| n.id.as_str())).collect();
+        for (name, commands) in events.scripts.iter() {
+            for command in commands.iter() {
+                match command {
+                    Command::Say(script) | Command::Ask(script, _) if !dialogues.has(script) => {
+                        return Err(format!("{}: {} says the unknown dialogue {}", path, name, script));
+                    }
+                    Command::Move(npc, _, _) | Command::Battle(npc) if !npcs.contains(npc.as_str()) => {
+                        return Err(format!("{}: {} names the unknown trainer {}", path, name, npc));
+                    }
+                    Command::Give(item, _) if items.get(item).is_none() => {
+                        return Err(format!("{}: {} gives the unknown item {}", path, name, item));
+                    }
+                    Command::Respawn(spawn) if !world.has_spawn(spawn) => {
+                        return Err(format!("{}: {} names the unknown spawn point {}", path, name, spawn));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (area, map) in world.areas() {
+            let triggers = map.triggers.iter().map(|t| &t.event).chain(map.talk_events.values());
+            if let Some(event) = triggers.into_iter().find(|e| !events.scripts.contains_key(*e)) {
+                return Err(format!("{}: there is no {} event for {}", path, event, area));
+            }
+        }
+
+        Ok(events)
+    }
+
+    fn parse(contents: &str) -> Result<Events, String> {
+        let mut scripts: HashMap<String, Vec<Command>> = HashMap::new();
+        let mut current: Option<String> = None;
+        // How many `if` commands of the current script are still open
+        let mut depth = 0;
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            let command = match v[..] {
+                [""] => continue,
+                ["event", name] => {
+                    if depth > 0 {
+                        return Err(format!("line {}: the event before {} has an if without an end", line_number, name));
+                    }
+                    if scripts.contains_key(name) {
+                        return Err(format!("line {}: there is already an event called {}", line_number, name));
+                    }
+                    scripts.insert(name.to_string(), Vec::new());
+                    current = Some(name.to_string());
+                    continue;
+                }
+                ["say", script] => Command::Say(script.to_string()),
+                ["ask", script, flag] => Command::Ask(script.to_string(), flag.to_string()),
+                ["move", npc, x, y] => Command::Move(npc.to_string(), parse_number(x, line_number)?, parse_number(y, line_number)?),
+                ["give", item, count] => Command::Give(item.to_string(), parse_number(count, line_number)?),
+                ["heal"] => Command::Heal,
+                ["respawn", spawn] => Command::Respawn(spawn.to_string()),
+                ["shop"] => Command::Shop,
+                ["battle", npc] => Command::Battle(npc.to_string()),
+                ["set", flag] => Command::Set(flag.to_string()),
+                ["clear", flag] => Command::Clear(flag.to_string()),
+                ["if", flag] => {
+                    depth += 1;
+                    match flag.strip_prefix('!') {
+                        Some(flag) => Command::If(flag.to_string(), false),
+                        None => Command::If(flag.to_string(), true),
+                    }
+                }
+                ["end"] => {
+                    if depth == 0 {
+                        return Err(format!("line {}: end without an if", line_number));
+                    }
+                    depth -= 1;
+                    Command::End
+                }
+                ["stop"] => Command::Stop,
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            };
+
+            let name = current.as_ref().ok_or(format!("line {}: commands have to come after an event record", line_number))?;
+            scripts.get_mut(name).unwrap().push(command);
+        }
+
+        if depth > 0 {
+            return Err(String::from("the last event has an if without an end"));
+        }
+
+        Ok(Events { scripts })
+    }
+
+    fn get(&self, name: &str) -> &[Command] {
+        match self.scripts.get(name) {
+            Some(commands) => commands,
+            None => panic!("There is no {} event", name),
+        }
+    }
+}
+
+/// What the scene running an event has to do for it
+pub enum Step<'a> {
+    /// Keep running the event on the next update
+    Wait,
+    /// Show a scene, like a dialogue or a battle; the event goes on once it is gone
+    Show(Transition<'a>),
+    Done,
+}
+
+/// An event being run, one command after another
+pub struct EventRunner {
+    commands: Vec<Command>,
+    next: usize,
+}
+
+impl EventRunner {
+    pub fn new(events: &Events, name: &str) -> EventRunner {
+        EventRunner {
+            commands: events.get(name).to_vec(),
+            next: 0,
+        }
+    }
+
+    /// The index of the command after the `End` that closes the `If` at `index`
+    fn after_end(&self, index: usize) -> usize {
+        let mut depth = 0;
+        for (i, command) in self.commands.iter().enumerate().skip(index) {
+            match command {
+                Command::If(_, _) => depth += 1,
+                Command::End if depth == 1 => return i + 1,
+                Command::End => depth -= 1,
+                _ => {}
+            }
+        }
+        self.commands.len()
+    }
+
+    /// Runs commands until one has to wait; saves the game once the event is over
+    ///
+    /// * `npcs` - The trainers of the scene, which commands can move and battle; those the
+    ///   scene does not have are skipped
+    pub fn step<'a>(&mut self, game: &mut Game<'a>, npcs: &mut [Npc], dt: f64) -> Result<Step<'a>, String> {
+        while let Some(command) = self.commands.get(self.next).cloned() {
+            self.next += 1;
+            match command {
+                Command::Say(script) => {
+                    let dialogue = DialogueScene::new(game, &script, &script, &[])?;
+                    return Ok(Step::Show(Transition::Push(Box::new(dialogue))));
+                }
+                Command::Ask(script, flag) => {
+                    let dialogue = DialogueScene::new(game, &script, &script, &[])?.then(move |game, answer| {
+                        if answer == Some(dialogue::YES) {
+                            game.flags.insert(flag);
+                        } else {
+                            game.flags.remove(&flag);
+                        }
+                        Transition::None
+                    });
+                    return Ok(Step::Show(Transition::Push(Box::new(dialogue))));
+                }
+                Command::Move(id, x, y) => {
+                    if let Some(npc) = npcs.iter_mut().find(|n| n.id == id) {
+                        if !npc.walk_towards((x, y), dt) {
+                            self.next -= 1;
+                            return Ok(Step::Wait);
+                        }
+                    }
+                }
+                Command::Give(item, count) => *game.items.entry(item).or_insert(0) += count,
+                Command::Heal => {
+                    game.heal_team();
+                    return Ok(Step::Show(Transition::Push(Box::new(overworld::HealScene::new()))));
+                }
+                Command::Respawn(spawn) => game.respawn = spawn,
+                Command::Shop => return Ok(Step::Show(Transition::Push(Box::new(ShopScene::new())))),
+                Command::Battle(id) => {
+                    if npcs.iter().any(|n| n.id == id) {
+                        return Ok(Step::Show(overworld::trainer_battle(game, id)));
+                    }
+                }
+                Command::Set(flag) => {
+                    game.flags.insert(flag);
+                }
+                Command::Clear(flag) => {
+                    game.flags.remove(&flag);
+                }
+                Command::If(flag, set) => {
+                    if game.flags.contains(&flag) != set {
+                        self.next = self.after_end(self.next - 1);
+                    }
+                }
+                Command::End => {}
+                Command::Stop => self.next = self.commands.len(),
+            }
+        }
+
+        game.save()?;
+        Ok(Step::Done)
+    }
+}
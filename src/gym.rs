@@ -1,102 +1,108 @@
 extern crate rand;
 
-use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
+use sdl2::rect::{Point, Rect};
 use sdl2::render::WindowCanvas;
 
+use crate::ai;
+use crate::assets::AssetManager;
+use crate::camera::Camera;
+use crate::dialogue::{self, DialogueScene};
+use crate::gym_layout::GymLayout;
 use crate::maze;
-use maze::Maze;
+use crate::menu;
+use crate::monster;
+use crate::overworld;
+use crate::player;
+use crate::input::{Action, Input};
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::npc::{Behavior, Challenge, Npc, NpcSpawn};
+use crate::parse::parse_number;
+use crate::sprite::{Animator, Facing};
+use crate::ui;
+use maze::{Feature, Maze, Side};
+
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::rc::Rc;
+
+pub const GYM_FILE: &str = "./data/gyms.txt";
+
+// The theme of a gym whose teams may have monsters of any type, like the champion's
+const MIXED_THEME: &str = "Mixed";
 
 const NPC_SIZE: i32 = 32;
 const PLAYER_SIZE: i32 = 32;
-const CAM_W: u32 = 1280;
-const CAM_H: u32 = 960;
-const BG_W: u32 = 1380;
-const BG_H: u32 = 1980;
-
-pub fn determine_cur_bg(player_x: i32, player_y: i32) -> Rect {
-
-    // This is the position rectangle for displaying the maze
-    let cur_bg = Rect::new (
-        (player_x + (PLAYER_SIZE / 2) - ((CAM_W / 2) as i32)).clamp(0, (BG_W - CAM_W) as i32),
-        (player_y + (PLAYER_SIZE / 2) - ((CAM_H / 2) as i32) + 450).clamp(0, (BG_H - CAM_H)  as i32),
-        CAM_W,
-        CAM_H,
-    );
-    return cur_bg;
-}
-
-pub fn draw_gym(wincan: &mut WindowCanvas, maze: Maze, gym_no: usize, player_x: i32, player_y: i32 ) -> Vec<Rect> {
-    let x_increment: u32;
-    let y_increment: u32;
-    let bg_color : Color;
-    let wall_color : Color; 
-    if gym_no == 0 {
-        bg_color = Color::RGB(0x57, 0x89, 0xA2);
-        wall_color = Color::RGB(0x60, 0x29, 0x8A);
-        x_increment = 140;
-        y_increment = 70;
-    } else if gym_no == 1 {
-        bg_color = Color::RGB(0x52, 0x83, 0x4A);
-        wall_color = Color::RGB(0xA8, 0x2D, 0x16);
-        x_increment = 212;
-        y_increment = 79;
-    } else if gym_no == 2 {
-        bg_color = Color::RGB(0x29, 0x39, 0x6A);
-        wall_color = Color::RGB(0xEE, 0xB0, 0x45);
-        x_increment = 79;
-        y_increment = 70;
-    } else {
-        bg_color = Color::RGB(0x5A, 0x4A, 0x41);
-        wall_color = Color::RGB(0xBD, 0x41, 0x41);
-        x_increment = 85;
-        y_increment = 70;
-    }
 
-    let gym_screen = Rect::new((0) as i32, (0) as i32, (1280) as u32, (720) as u32);
-    let texture_creator = wincan.texture_creator();
-    wincan.set_draw_color(bg_color);
-    wincan.fill_rect(gym_screen).unwrap();
-    wincan.set_draw_color(wall_color);
+// How many pixels ahead gym trainers spot the player from
+const TRAINER_SIGHT: u32 = 160;
+
+// The sizes of the keys, switches and teleport pads in the middle of maze cells
+const KEY_SIZE: u32 = 16;
+const SWITCH_SIZE: u32 = 24;
+const PAD_SIZE: u32 = 40;
+
+// The most room the map of a maze takes up, in the top left corner of the screen
+const MINIMAP_SIZE: (u32, u32) = (320, 260);
+const MINIMAP_MARGIN: i32 = 20;
+
+// The start sign by the entrance, which sticks out to the right of the maze; x is from the maze's right edge
+const START_SIGN: (i32, i32, u32, u32) = (10, 0, 140, 80);
+
+/// The number of gyms with badges; the entry after them in `data/gyms.txt` is the champion
+pub const BADGE_COUNT: usize = 4;
+
+/// The width and height of a maze cell in a gym
+fn cell_size(layout: &GymLayout) -> (u32, u32) {
+    (layout.cell_width, layout.cell_height)
+}
 
-    let mut collision_vec = Vec::new();
+/// The right edge of the maze, where the player comes in from the start sign
+fn maze_right(gym_maze: &Maze, layout: &GymLayout) -> i32 {
+    (gym_maze.maze_width as u32 * layout.cell_width) as i32
+}
+
+fn start_sign(gym_maze: &Maze, layout: &GymLayout) -> Rect {
+    Rect::new(maze_right(gym_maze, layout) + START_SIGN.0, START_SIGN.1, START_SIGN.2, START_SIGN.3)
+}
+
+/// The width and height of a gym: the maze, along with the start sign next to it
+pub fn gym_size(gym_maze: &Maze, layout: &GymLayout) -> (u32, u32) {
+    let (x_increment, y_increment) = cell_size(layout);
+    let width = (gym_maze.maze_width as u32 * x_increment).max(start_sign(gym_maze, layout).right() as u32);
+    (width, gym_maze.maze_height as u32 * y_increment + 5)
+}
+
+/// The floor and wall colors of a gym
+fn gym_colors(layout: &GymLayout) -> (Color, Color) {
+    let ((floor_r, floor_g, floor_b), (wall_r, wall_g, wall_b)) = (layout.floor, layout.wall);
+    (Color::RGB(floor_r, floor_g, floor_b), Color::RGB(wall_r, wall_g, wall_b))
+}
 
-    let gym_maze = maze;
+/// Returns the walls of the maze
+fn maze_walls(gym_maze: &Maze, layout: &GymLayout) -> Vec<Rect> {
+    let (x_increment, y_increment) = cell_size(layout);
+    let mut walls = Vec::new();
 
     let mut y1 = 0;
     let mut y2 = y_increment as i32;
 
-    let cur_bg = determine_cur_bg(player_x, player_y);
-
-    // The walls are making up the "background", we need to push their position in regards to the camera to the collision vec
     for row in 0..gym_maze.maze_height {
         let mut x_tw_lw_bw = 0;
         let mut x_rw = x_increment as i32;
-        //let mut row = 0;
         for container in 0..gym_maze.maze[row].len() {
-            if row == 0 {
-                if gym_maze.maze[row][container].top_wall {
-                    let container_to_add = Rect::new(x_tw_lw_bw - cur_bg.x(), y1 - cur_bg.y(), x_increment, 5);
-                    collision_vec.push(container_to_add.clone());
-                    wincan.fill_rect(container_to_add).unwrap();
-                }
+            if row == 0 && gym_maze.maze[row][container].top_wall {
+                walls.push(Rect::new(x_tw_lw_bw, y1, x_increment, 5));
             }
             if gym_maze.maze[row][container].left_wall {
-                let container_to_add = Rect::new(x_tw_lw_bw - cur_bg.x(), y1 - cur_bg.y(), 5, y_increment + 5);
-                collision_vec.push(container_to_add.clone());
-                wincan.fill_rect(container_to_add).unwrap();
+                walls.push(Rect::new(x_tw_lw_bw, y1, 5, y_increment + 5));
             }
-
             if gym_maze.maze[row][container].right_wall {
-                let container_to_add = Rect::new(x_rw - cur_bg.x(), y1 - cur_bg.y(), 5, y_increment + 5);
-                collision_vec.push(container_to_add.clone());
-                wincan.fill_rect(container_to_add).unwrap();
+                walls.push(Rect::new(x_rw, y1, 5, y_increment + 5));
             }
             if gym_maze.maze[row][container].bottom_wall {
-                let container_to_add = Rect::new(x_tw_lw_bw - cur_bg.x(), y2 - cur_bg.y(), x_increment, 5);
-                collision_vec.push(container_to_add.clone());
-                wincan.fill_rect(container_to_add).unwrap();
+                walls.push(Rect::new(x_tw_lw_bw, y2, x_increment, 5));
             }
 
             x_tw_lw_bw += x_increment as i32;
@@ -105,115 +111,939 @@ pub fn draw_gym(wincan: &mut WindowCanvas, maze: Maze, gym_no: usize, player_x:
         y1 += y_increment as i32;
         y2 += y_increment as i32;
     }
-    let start_sheet = texture_creator.load_texture("images/start.png").unwrap();
-    let start_box = Rect::new(1270, 0, 140, 80);
-    collision_vec.push(start_box.clone());
-    wincan.copy(&start_sheet, None, start_box).unwrap();
+    walls
+}
+
+/// Returns everything the player collides with in the gym
+pub fn gym_walls(maze: &Maze, layout: &GymLayout) -> Vec<Rect> {
+    let (_, y_increment) = cell_size(layout);
+    let mut collision_vec = maze_walls(maze, layout);
+
+    // The start sign and the entrance leading to it from the top right cell
+    let right = maze_right(maze, layout);
+    collision_vec.push(start_sign(maze, layout));
+    collision_vec.push(Rect::new(right, 0, START_SIGN.0 as u32, 5));
+    collision_vec.push(Rect::new(right, y_increment as i32, START_SIGN.0 as u32 + START_SIGN.2, 5));
+
+    collision_vec
+}
+
+/// The floor of a cell of the maze, given as (row, column)
+fn cell_rect(layout: &GymLayout, (row, column): (usize, usize)) -> Rect {
+    let (x_increment, y_increment) = cell_size(layout);
+    Rect::new(column as i32 * x_increment as i32, row as i32 * y_increment as i32, x_increment, y_increment)
+}
+
+/// Where the wall in a side of a cell stands, the same as `maze_walls` puts it
+fn side_rect(layout: &GymLayout, cell: (usize, usize), side: Side) -> Rect {
+    let floor = cell_rect(layout, cell);
+    match side {
+        Side::Top => Rect::new(floor.x(), floor.y(), floor.width(), 5),
+        Side::Bottom => Rect::new(floor.x(), floor.bottom(), floor.width(), 5),
+        Side::Left => Rect::new(floor.x(), floor.y(), 5, floor.height() + 5),
+        Side::Right => Rect::new(floor.right(), floor.y(), 5, floor.height() + 5),
+    }
+}
 
-    let top_entrance = Rect::new(1150,0, x_increment + 20, 5);
-    collision_vec.push(top_entrance.clone());
+/// A square of the given size in the middle of a cell
+fn centered(layout: &GymLayout, cell: (usize, usize), size: u32) -> Rect {
+    let mut square = Rect::new(0, 0, size, size);
+    square.center_on(cell_rect(layout, cell).center());
+    square
+}
 
-    
-    let bottom_entrance = Rect::new(1150, y_increment as i32, x_increment + 20, 5);
-    collision_vec.push(bottom_entrance.clone());
+/// The cell of the maze a point is in, if it is in the maze
+fn cell_at(maze: &Maze, layout: &GymLayout, point: Point) -> Option<(usize, usize)> {
+    if point.x() < 0 || point.y() < 0 {
+        return None;
+    }
+    let (x_increment, y_increment) = cell_size(layout);
+    let (row, column) = ((point.y() as u32 / y_increment) as usize, (point.x() as u32 / x_increment) as usize);
+    if row < maze.maze_height && column < maze.maze_width {
+        Some((row, column))
+    } else {
+        None
+    }
+}
 
-    return collision_vec;
+/// The features of the maze, along with the cells they are in
+fn features(maze: &Maze) -> impl Iterator<Item = ((usize, usize), Feature)> + '_ {
+    maze.maze.iter().enumerate().flat_map(|(row, containers)| {
+        containers
+            .iter()
+            .enumerate()
+            .filter(|(_, container)| container.feature != Feature::None)
+            .map(move |(column, container)| ((row, column), container.feature))
+    })
 }
 
-pub fn display_gym_menu(wincan: &mut WindowCanvas) -> Result<(), String> {
-    let texture_creator = wincan.texture_creator();
-    let display_gym_box = texture_creator
-        .load_texture("images/enterbuilding.png")
-        .unwrap();
+/// What the player has done to the puzzles of a gym's maze since they came in
+#[derive(Default)]
+pub struct PuzzleState {
+    /// The numbers of the keys picked up
+    keys: HashSet<usize>,
+    /// The numbers of the switches that are on
+    switches: HashSet<usize>,
+    /// The switch or teleport pad the player is standing on, which does nothing again until they step off it
+    standing_on: Option<(usize, usize)>,
+}
 
-    let display_box = Rect::new(500, 200, 200, 200);
-    wincan.copy(&display_gym_box, None, display_box)?;
+/// The locked doors, standing switch walls and gates that the player cannot walk through
+///
+/// A gate only stops a player coming from the far side of it who is not already going through it.
+///
+/// * `player` - Where the player was before their last step
+fn puzzle_walls(maze: &Maze, layout: &GymLayout, puzzles: &PuzzleState, player: Rect) -> Vec<Rect> {
+    features(maze)
+        .filter_map(|(cell, feature)| match feature {
+            Feature::Door(side, number) if !puzzles.keys.contains(&number) => Some(side_rect(layout, cell, side)),
+            Feature::SwitchWall(side, number) if !puzzles.switches.contains(&number) => Some(side_rect(layout, cell, side)),
+            Feature::Gate(side) => {
+                let gate = side_rect(layout, cell, side);
+                let leaving = cell_rect(layout, cell).contains_point(player.center()) || player.has_intersection(gate);
+                if leaving {
+                    None
+                } else {
+                    Some(gate)
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
 
+/// Draws the keys, switches and teleport pads in the maze, along with its locked doors,
+/// standing switch walls and gates
+fn draw_puzzles(
+    wincan: &mut WindowCanvas,
+    camera: &Camera,
+    maze: &Maze,
+    layout: &GymLayout,
+    puzzles: &PuzzleState,
+) -> Result<(), String> {
+    for (cell, feature) in features(maze) {
+        match feature {
+            Feature::None => {}
+            Feature::Key(number) => {
+                if !puzzles.keys.contains(&number) {
+                    wincan.set_draw_color(Color::RGB(0xF5, 0xC5, 0x18));
+                    wincan.fill_rect(camera.to_screen(centered(layout, cell, KEY_SIZE)))?;
+                }
+            }
+            Feature::Door(side, number) => {
+                if !puzzles.keys.contains(&number) {
+                    wincan.set_draw_color(Color::RGB(0x8B, 0x5A, 0x2B));
+                    wincan.fill_rect(camera.to_screen(side_rect(layout, cell, side)))?;
+                }
+            }
+            Feature::Switch(number) => {
+                wincan.set_draw_color(Color::BLACK);
+                wincan.fill_rect(camera.to_screen(centered(layout, cell, SWITCH_SIZE)))?;
+                let on = puzzles.switches.contains(&number);
+                wincan.set_draw_color(if on { Color::RGB(0x41, 0xC5, 0x52) } else { Color::RGB(0xE0, 0x39, 0x39) });
+                wincan.fill_rect(camera.to_screen(centered(layout, cell, SWITCH_SIZE - 6)))?;
+            }
+            Feature::SwitchWall(side, number) => {
+                if !puzzles.switches.contains(&number) {
+                    wincan.set_draw_color(Color::RGB(0x39, 0xC5, 0xE6));
+                    wincan.fill_rect(camera.to_screen(side_rect(layout, cell, side)))?;
+                }
+            }
+            Feature::Gate(side) => {
+                // Gates are dashed, so they look like they can be gone through
+                let gate = side_rect(layout, cell, side);
+                wincan.set_draw_color(Color::WHITE);
+                let dashes = (gate.width().max(gate.height()) / 10) as i32;
+                for dash in 0..dashes {
+                    let dash = if gate.width() > gate.height() {
+                        Rect::new(gate.x() + dash * 10, gate.y(), 5, 5)
+                    } else {
+                        Rect::new(gate.x(), gate.y() + dash * 10, 5, 5)
+                    };
+                    wincan.fill_rect(camera.to_screen(dash))?;
+                }
+            }
+            Feature::Teleport(_) => {
+                wincan.set_draw_color(Color::RGB(0xA0, 0x52, 0xD5));
+                for inset in [0, 8, 16] {
+                    wincan.draw_rect(camera.to_screen(centered(layout, cell, PAD_SIZE - inset)))?;
+                }
+            }
+        }
+    }
     Ok(())
 }
 
-pub fn display_exit_gym_menu(wincan: &mut WindowCanvas) -> Result<(), String> {
-    let texture_creator = wincan.texture_creator();
-    let display_gym_box = texture_creator.load_texture("images/exit_gym.png").unwrap();
+/// Draws the floor and walls of the gym that the camera sees, along with its puzzles
+pub fn draw_gym(
+    wincan: &mut WindowCanvas,
+    assets: &AssetManager,
+    camera: &Camera,
+    maze: &Maze,
+    layout: &GymLayout,
+    puzzles: &PuzzleState,
+) -> Result<(), String> {
+    let (bg_color, wall_color) = gym_colors(layout);
 
-    let display_box = Rect::new(400, 200, 500, 300);
-    wincan.copy(&display_gym_box, None, display_box)?;
+    wincan.set_draw_color(bg_color);
+    wincan.clear();
+    wincan.set_draw_color(wall_color);
+    for wall in maze_walls(maze, layout).into_iter().filter(|w| camera.sees(w)) {
+        wincan.fill_rect(camera.to_screen(wall))?;
+    }
+    draw_puzzles(wincan, camera, maze, layout, puzzles)?;
+
+    wincan.copy(assets.texture("images/start.png"), None, camera.to_screen(start_sign(maze, layout)))?;
 
     Ok(())
 }
 
-pub fn draw_npc(wincan: &mut WindowCanvas, maze: &Maze, gym_no: usize, player_x: i32, player_y: i32) -> (Vec<Rect>, Rect) {
-    let x_increment: u32;
-    let y_increment: u32;
-    let y_adjust: i32;
-    if gym_no == 0 {
-        x_increment = 140;
-        y_increment = 70;
-        y_adjust = 2;
-    } else if gym_no == 1 {
-        x_increment = 212;
-        y_increment = 79;
-        y_adjust = 2;
-    } else if gym_no == 2 {
-        x_increment = 79;
-        y_increment = 70;
-        y_adjust = 2;
-    } else {
-        x_increment = 85;
-        y_increment = 70;
-        y_adjust = 2;
+/// Draws a map of the cells of the maze the player has been in, along with where they are,
+/// the trainers in those cells, and the leader once found; the rest of the maze stays dark
+fn draw_minimap(
+    wincan: &mut WindowCanvas,
+    game: &Game,
+    gym_no: usize,
+    player: Rect,
+    trainers: &[Npc],
+    leader: Rect,
+) -> Result<(), String> {
+    let maze = &game.gym_mazes[gym_no];
+    let layout = game.layouts.get(gym_no);
+    let explored = &game.gym_explored[gym_no];
+    let scale = (MINIMAP_SIZE.0 / maze.maze_width as u32).min(MINIMAP_SIZE.1 / maze.maze_height as u32).max(3);
+    let thickness = (scale / 6).max(1);
+    let cell = |(row, column): (usize, usize)| {
+        Rect::new(MINIMAP_MARGIN + (column as u32 * scale) as i32, MINIMAP_MARGIN + (row as u32 * scale) as i32, scale, scale)
+    };
+
+    wincan.set_draw_color(Color::RGBA(0, 0, 0, 200));
+    wincan.fill_rect(Rect::new(
+        MINIMAP_MARGIN - 4,
+        MINIMAP_MARGIN - 4,
+        maze.maze_width as u32 * scale + 8,
+        maze.maze_height as u32 * scale + 8,
+    ))?;
+    let (floor_color, wall_color) = gym_colors(layout);
+    for (row, cells) in explored.iter().enumerate() {
+        for (column, _) in cells.iter().enumerate().filter(|(_, explored)| **explored) {
+            let floor = cell((row, column));
+            wincan.set_draw_color(floor_color);
+            wincan.fill_rect(floor)?;
+            let container = &maze.maze[row][column];
+            let walls = [
+                (container.top_wall, Rect::new(floor.x(), floor.y(), scale, thickness)),
+                (container.bottom_wall, Rect::new(floor.x(), floor.bottom() - thickness as i32, scale, thickness)),
+                (container.left_wall, Rect::new(floor.x(), floor.y(), thickness, scale)),
+                (container.right_wall, Rect::new(floor.right() - thickness as i32, floor.y(), thickness, scale)),
+            ];
+            wincan.set_draw_color(wall_color);
+            for (_, wall) in walls.iter().filter(|(standing, _)| *standing) {
+                wincan.fill_rect(*wall)?;
+            }
+        }
     }
 
-    let texture_creator = wincan.texture_creator();
-    let npc_sheet = texture_creator.load_texture("images/NPC_1.png").unwrap();
+    // A dot in the middle of the cell something is in, if the player has been there
+    let mut draw_dot = |at: Rect, color: Color| -> Result<(), String> {
+        match cell_at(maze, layout, at.center()) {
+            Some((row, column)) if explored[row][column] => {
+                let mut dot = Rect::new(0, 0, (scale / 2).max(2), (scale / 2).max(2));
+                dot.center_on(cell((row, column)).center());
+                wincan.set_draw_color(color);
+                wincan.fill_rect(dot)
+            }
+            _ => Ok(()),
+        }
+    };
+    for npc in trainers.iter() {
+        let beaten = game.defeated_trainers.contains(&npc.id);
+        draw_dot(npc.rect(), if beaten { Color::RGB(0x80, 0x80, 0x80) } else { Color::RGB(0xE0, 0x39, 0x39) })?;
+    }
+    draw_dot(leader, Color::RGB(0xF5, 0xC5, 0x18))?;
+    draw_dot(player, Color::WHITE)
+}
+
+/// Returns the gym trainers and the leader
+pub fn gym_npcs(gym_maze: &Maze, layout: &GymLayout) -> (Vec<Rect>, Rect) {
+    let (x_increment, y_increment) = cell_size(layout);
+    let y_adjust: i32 = 2;
 
     let mut npc_collection = Vec::new();
-    let gym_maze = maze;
 
     let mut top_y = 0;
 
-    let x_adjust: i32 = (1280 / gym_maze.maze_width / 2) as i32 - 20 as i32;
+    let x_adjust: i32 = (x_increment / 2) as i32 - 20;
 
     let mut left_x;
-    let cur_bg = determine_cur_bg(player_x, player_y);
 
     for row in 0..gym_maze.maze_height - 1 {
         left_x = 0;
         for container in 0..gym_maze.maze[row].len() {
-            if gym_maze.maze[row][container].determine_corner()
-                && gym_maze.maze[row][container].let_spawn
-            {
-                let npc_container = Rect::new(
-                    left_x + 6 + x_adjust - cur_bg.x(),
-                    top_y + 6 + y_adjust - cur_bg.y(),
+            if gym_maze.maze[row][container].has_trainer() {
+                npc_collection.push(Rect::new(
+                    left_x + 6 + x_adjust,
+                    top_y + 6 + y_adjust,
                     NPC_SIZE as u32,
                     NPC_SIZE as u32,
-                );
-                npc_collection.push(npc_container.clone());
-                wincan.copy(&npc_sheet, None, npc_container).unwrap();
+                ));
             }
             left_x += x_increment as i32;
         }
         top_y += y_increment as i32;
     }
 
-    let npc_container = Rect::new(
-        6 + x_adjust - cur_bg.x(),
-        top_y + 6 + y_adjust - cur_bg.y(),
+    let boss = Rect::new(
+        6 + x_adjust,
+        top_y + 6 + y_adjust,
         NPC_SIZE as u32,
         NPC_SIZE as u32,
     );
-    let npc_sheet = texture_creator.load_texture("images/boss.png").unwrap();
-    wincan.copy(&npc_sheet, None, npc_container).unwrap();
 
-    return (npc_collection, npc_container);
+    (npc_collection, boss)
+}
+
+/// The trainers of a gym, placed where `gym_npcs` puts them, each looking around the maze
+fn gym_trainers(gym_maze: &Maze, layout: &GymLayout, gym_no: usize) -> Vec<Npc> {
+    let facings = [Facing::Down, Facing::Left, Facing::Up, Facing::Right];
+    gym_npcs(gym_maze, layout)
+        .0
+        .iter()
+        .enumerate()
+        .map(|(index, rect)| {
+            // Trainers start out looking different ways, then turn through all of them
+            let turns: Vec<Facing> = (0..facings.len()).map(|turn| facings[(index + turn) % facings.len()]).collect();
+            let spawn = NpcSpawn {
+                id: trainer_id(gym_no, index),
+                x: rect.x(),
+                y: rect.y(),
+                facing: turns[0],
+                sight: TRAINER_SIGHT,
+                behavior: Behavior::Turn(turns),
+            };
+            Npc::new(&spawn, NPC_SIZE as u32)
+        })
+        .collect()
+}
+
+/// A monster on a designed gym team
+pub struct GymMonster {
+    pub species: String,
+    pub level: usize,
+    pub moves: Option<Vec<String>>,
+}
+
+/// The designed contents of a gym; loaded from `data/gyms.txt`
+pub struct GymInfo {
+    pub theme: String,
+    pub badge: String,
+    pub reward: usize,
+    pub leader_profile: String,
+    pub trainer_teams: Vec<Vec<GymMonster>>,
+    pub leader_team: Vec<GymMonster>,
+}
+
+/// Parses a team member written as `species:level` or `species:level:move;move;move;move`,
+/// making sure the species and its moves exist
+fn parse_gym_monster(
+    s: &str,
+    line_number: usize,
+    monsters: &HashMap<String, monster::Monster>,
+    moves_map: &HashMap<String, monster::Move>,
+) -> Result<GymMonster, String> {
+    let v = s.split(':').collect::<Vec<&str>>();
+    let (species, level, moves) = match v[..] {
+        [species, level] => (species, level, None),
+        [species, level, moves] => (species, level, Some(moves)),
+        _ => return Err(format!("line {}: cannot read team member {}", line_number, s)),
+    };
+    if !monsters.contains_key(species) {
+        return Err(format!("line {}: there is no {} monster", line_number, species));
+    }
+    let moves = match moves {
+        Some(moves) => {
+            let moves: Vec<String> = moves.split(';').map(|m| m.trim().to_string()).collect();
+            if moves.len() != 4 {
+                return Err(format!("line {}: {} needs 4 moves, not {}", line_number, species, moves.len()));
+            }
+            if let Some(unknown) = moves.iter().find(|m| !moves_map.contains_key(*m)) {
+                return Err(format!("line {}: there is no {} move", line_number, unknown));
+            }
+            Some(moves)
+        }
+        None => None,
+    };
+    Ok(GymMonster {
+        species: species.to_string(),
+        level: parse_number(level, line_number)?,
+        moves,
+    })
+}
+
+/// Parses the team of a trainer or leader, whose monsters all have to be of the gym's theme
+fn parse_gym_team(
+    members: &[&str],
+    line_number: usize,
+    theme: &str,
+    monsters: &HashMap<String, monster::Monster>,
+    moves_map: &HashMap<String, monster::Move>,
+) -> Result<Vec<GymMonster>, String> {
+    if members.is_empty() {
+        return Err(format!("line {}: a team needs at least one monster", line_number));
+    }
+    let team = members
+        .iter()
+        .map(|m| parse_gym_monster(m, line_number, monsters, moves_map))
+        .collect::<Result<Vec<GymMonster>, String>>()?;
+    if theme != MIXED_THEME {
+        if let Some(m) = team.iter().find(|m| monsters[&m.species].monster_type != theme) {
+            return Err(format!("line {}: {} is not a {} type", line_number, m.species, theme));
+        }
+    }
+    Ok(team)
+}
+
+/// Loads the theme, badge, reward and teams of every gym, indexed by gym number
+///
+/// The file has one `gym,number,theme,badge,reward,leader_profile`, `trainer,number,team...` or
+/// `leader,number,team...` record per line after a header line. Gyms are listed in order, and
+/// each gym's trainers and leader come after its `gym` record. Every team member has to be of
+/// the gym's theme, unless the theme is `Mixed`.
+pub fn load_gyms(
+    path: &str,
+    monsters: &HashMap<String, monster::Monster>,
+    moves_map: &HashMap<String, monster::Move>,
+) -> Result<Vec<GymInfo>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+    parse_gyms(&contents, monsters, moves_map).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn parse_gyms(
+    contents: &str,
+    monsters: &HashMap<String, monster::Monster>,
+    moves_map: &HashMap<String, monster::Move>,
+) -> Result<Vec<GymInfo>, String> {
+    let mut gyms: Vec<GymInfo> = Vec::new();
+
+    // Skip the header line
+    for (index, line) in contents.lines().enumerate().skip(1) {
+        let line_number = index + 1;
+        let v = line.trim().split(',').collect::<Vec<&str>>();
+        if v[..] == [""] {
+            continue;
+        }
+        if v.len() < 2 {
+            return Err(format!("line {}: cannot read {}", line_number, line));
+        }
+        let gym_no: usize = parse_number(v[1], line_number)?;
+
+        match v[0] {
+            "gym" => {
+                if gym_no != gyms.len() {
+                    return Err(format!("line {}: expected gym {}", line_number, gyms.len()));
+                }
+                match v[2..] {
+                    [theme, badge, reward, leader_profile] => gyms.push(GymInfo {
+                        theme: theme.trim().to_string(),
+                        badge: badge.trim().to_string(),
+                        reward: parse_number(reward, line_number)?,
+                        leader_profile: leader_profile.trim().to_string(),
+                        trainer_teams: Vec::new(),
+                        leader_team: Vec::new(),
+                    }),
+                    _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+                }
+            }
+            "trainer" | "leader" => {
+                let gym = gyms
+                    .get_mut(gym_no)
+                    .ok_or_else(|| format!("line {}: gym {} has not been listed yet", line_number, gym_no))?;
+                let team = parse_gym_team(&v[2..], line_number, &gym.theme, monsters, moves_map)?;
+                if v[0] == "trainer" {
+                    gym.trainer_teams.push(team);
+                } else {
+                    gym.leader_team = team;
+                }
+            }
+            record => return Err(format!("line {}: there is no {} record", line_number, record)),
+        }
+    }
+
+    if let Some(gym_no) = gyms.iter().position(|g| g.leader_team.is_empty()) {
+        return Err(format!("gym {} has no leader", gym_no));
+    }
+    Ok(gyms)
+}
+
+/// Returns a designed team in the form used by battles
+pub fn gym_team(team: &[GymMonster]) -> Vec<(String, f32, usize)> {
+    team.iter()
+        .map(|m| (m.species.clone(), 100.0, m.level * 10))
+        .collect()
+}
+
+/// Returns the designed movesets of a team, keyed by species
+///
+/// * `team` - The designed team
+/// * `moves_map` - Maps move names onto their Move objects
+pub fn gym_movesets<'a>(
+    team: &[GymMonster],
+    moves_map: &'a HashMap<String, monster::Move>,
+) -> HashMap<String, Vec<&'a monster::Move>> {
+    team.iter()
+        .filter_map(|m| {
+            m.moves
+                .as_ref()
+                .map(|moves| (m.species.clone(), moves.iter().map(|d| &moves_map[d]).collect()))
+        })
+        .collect()
+}
+
+/// A gym can be entered once the badges of every gym before it have been earned
+pub fn gym_unlocked(badges: &HashSet<u32>, gym_no: usize) -> bool {
+    (0..gym_no as u32).all(|g| badges.contains(&g))
+}
+
+fn theme_color(theme: &str) -> Color {
+    match theme {
+        "Grass" => Color::RGB(0x52, 0xA8, 0x4A),
+        "Fire" => Color::RGB(0xE0, 0x52, 0x31),
+        "Water" => Color::RGB(0x39, 0x7B, 0xE6),
+        "Electric" => Color::RGB(0xEE, 0xC8, 0x31),
+        "Ground" => Color::RGB(0x9C, 0x73, 0x41),
+        "Flying" => Color::RGB(0x91, 0xD5, 0xFF),
+        _ => Color::RGB(0xC5, 0xBD, 0xA4),
+    }
 }
 
-pub fn gym_coordinates(gym_index: usize) -> (i32, i32) {
-    return match gym_index {
-        0 => {(410, 260)}
-        1 => {(1190, 600)}
-        2 => {(880, 400)}
-        _ => {(380, 600)}
+/// Draws the badges of every gym, with the ones not yet earned grayed out
+///
+/// * `gyms` - The gyms that award badges
+/// * `badges` - The gym numbers whose badges were earned
+pub fn draw_badge_case(
+    wincan: &mut WindowCanvas,
+    assets: &AssetManager,
+    gyms: &[GymInfo],
+    badges: &HashSet<u32>,
+) -> Result<(), String> {
+    wincan.set_draw_color(Color::RGB(0x20, 0x41, 0x6a));
+    wincan.fill_rect(Rect::new(240, 160, 800, 400))?;
+    ui::draw_text(wincan, assets, "Badge Case", Color::RGB(0xbd, 0xcd, 0xde), Rect::new(440, 180, 400, 50))?;
+
+    for (i, gym) in gyms.iter().enumerate() {
+        let x = 280 + 190 * i as i32;
+        wincan.set_draw_color(Color::RGB(0x39, 0x7B, 0xB4));
+        wincan.fill_rect(Rect::new(x, 260, 160, 250))?;
+
+        if badges.contains(&(i as u32)) {
+            wincan.set_draw_color(theme_color(&gym.theme));
+            wincan.fill_rect(Rect::new(x + 30, 290, 100, 100))?;
+            ui::draw_text(wincan, assets, &gym.badge, Color::BLACK, Rect::new(x + 5, 410, 150, 35))?;
+        } else {
+            wincan.set_draw_color(Color::RGB(0x5A, 0x5A, 0x5A));
+            wincan.fill_rect(Rect::new(x + 30, 290, 100, 100))?;
+            ui::draw_text(wincan, assets, "???", Color::BLACK, Rect::new(x + 5, 410, 150, 35))?;
+        }
+        ui::draw_text(wincan, assets, &format!("{} Gym", gym.theme), Color::RGB(0xbd, 0xcd, 0xde), Rect::new(x + 5, 455, 150, 30))?;
+    }
+
+    Ok(())
+}
+
+/// Where the player can leave the gym, next to the start sign
+fn exit_box(gym_maze: &Maze, layout: &GymLayout) -> Rect {
+    Rect::new(maze_right(gym_maze, layout) - 20, 0, 100, 50)
+}
+
+/// Makes a new maze for a gym, sized for the difficulty and the badges the player has, so
+/// that it is different the next time the player comes in
+pub fn reload_maze(game: &mut Game, gym_no: usize) {
+    forget_trainers(game, gym_no);
+    let layout = game.layouts.get(gym_no);
+    game.gym_mazes[gym_no] = layout.maze(game.difficulty, badges_since(game, gym_no), &mut rand::thread_rng());
+    game.gym_explored[gym_no] = unexplored(&game.gym_mazes[gym_no]);
+}
+
+/// Makes new mazes for every gym, after the difficulty is picked
+pub fn reload_mazes(game: &mut Game) {
+    for gym_no in 0..BADGE_COUNT {
+        forget_trainers(game, gym_no);
+    }
+    game.gym_mazes = (0..BADGE_COUNT)
+        .map(|gym_no| game.layouts.get(gym_no).maze(game.difficulty, badges_since(game, gym_no), &mut rand::thread_rng()))
+        .collect();
+    game.gym_explored = game.gym_mazes.iter().map(unexplored).collect();
+}
+
+/// The badges the player has earned since first getting to a gym, which its maze grows with
+fn badges_since(game: &Game, gym_no: usize) -> usize {
+    game.player_badges.iter().filter(|b| **b as usize >= gym_no).count()
+}
+
+/// The id of a trainer in the current maze of a gym, numbered in the order they are placed
+fn trainer_id(gym_no: usize, index: usize) -> String {
+    format!("gym_{}_trainer_{}", gym_no, index)
+}
+
+/// Forgets which trainers were beaten in the maze of a gym before it is remade, so the trainers
+/// of the new maze are ready to battle; quests still count them
+fn forget_trainers(game: &mut Game, gym_no: usize) {
+    let prefix = format!("gym_{}_trainer_", gym_no);
+    let before = game.defeated_trainers.len();
+    game.defeated_trainers.retain(|t| !t.starts_with(&prefix));
+    let forgotten = (before - game.defeated_trainers.len()) as u32;
+    if forgotten > 0 {
+        *game.quest_log.gym_trainers.entry(gym_no).or_insert(0) += forgotten;
+    }
+}
+
+/// A map of a maze with none of it explored yet
+fn unexplored(maze: &Maze) -> Vec<Vec<bool>> {
+    vec![vec![false; maze.maze_width]; maze.maze_height]
+}
+
+/// Walking through the maze of a gym
+pub struct GymScene {
+    gym_no: usize,
+    player_box: Rect,
+    body: player::Body,
+    direction: (i32, i32),
+    wall_collision: bool,
+    player_sprite: Animator,
+    trainers: Vec<Npc>,
+    /// A trainer who spotted the player and is coming over to battle
+    challenge: Option<Challenge>,
+    leader_sprite: Animator,
+    /// Set once the player answers that they want to leave the gym
+    leaving: Rc<Cell<bool>>,
+    puzzles: PuzzleState,
+    /// Whether the map of the maze is shown
+    show_map: bool,
+}
+
+impl GymScene {
+    pub fn new(game: &Game, gym_no: usize) -> GymScene {
+        // The player starts inside the top right cell of the maze, by the start sign
+        let start_x = maze_right(&game.gym_mazes[gym_no], game.layouts.get(gym_no)) - 60;
+        GymScene {
+            gym_no,
+            player_box: Rect::new(start_x, 7, PLAYER_SIZE as u32, PLAYER_SIZE as u32),
+            body: player::Body::new(start_x, 7),
+            direction: (0, 0),
+            wall_collision: false,
+            player_sprite: Animator::new("player", Facing::Down),
+            trainers: gym_trainers(&game.gym_mazes[gym_no], game.layouts.get(gym_no), gym_no),
+            challenge: None,
+            leader_sprite: Animator::new("leader", Facing::Down),
+            leaving: Rc::new(Cell::new(false)),
+            puzzles: PuzzleState::default(),
+            show_map: false,
+        }
+    }
+
+    fn exit_box(&self, game: &Game) -> Rect {
+        exit_box(&game.gym_mazes[self.gym_no], game.layouts.get(self.gym_no))
+    }
+
+    /// Picks up a key the player walks over, and sets off a switch or teleport pad they step onto
+    fn step_on_puzzles<'a>(&mut self, game: &Game<'a>) -> Result<Transition<'a>, String> {
+        let maze = &game.gym_mazes[self.gym_no];
+        let layout = game.layouts.get(self.gym_no);
+        let feet = self.player_box.center();
+        let mut standing_on = None;
+        for (cell, feature) in features(maze) {
+            let stepped_on = self.puzzles.standing_on != Some(cell);
+            match feature {
+                Feature::Key(number)
+                    if !self.puzzles.keys.contains(&number)
+                        && self.player_box.has_intersection(centered(layout, cell, KEY_SIZE)) =>
+                {
+                    self.puzzles.keys.insert(number);
+                    self.body.stop();
+                    let message = DialogueScene::message(game, "", "You picked up a key! It opens a door in the maze.")?;
+                    return Ok(Transition::Push(Box::new(message)));
+                }
+                Feature::Switch(number) if centered(layout, cell, SWITCH_SIZE).contains_point(feet) => {
+                    standing_on = Some(cell);
+                    if stepped_on && !self.puzzles.switches.remove(&number) {
+                        self.puzzles.switches.insert(number);
+                    }
+                }
+                Feature::Teleport(to) if centered(layout, cell, PAD_SIZE).contains_point(feet) => {
+                    standing_on = Some(cell);
+                    if stepped_on {
+                        let arrival = centered(layout, to, PLAYER_SIZE as u32);
+                        self.body.place(arrival.x(), arrival.y());
+                        self.player_box.reposition((arrival.x(), arrival.y()));
+                        standing_on = None;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.puzzles.standing_on = standing_on;
+        Ok(Transition::None)
+    }
+
+    fn leader(&self, game: &Game) -> Rect {
+        gym_npcs(&game.gym_mazes[self.gym_no], game.layouts.get(self.gym_no)).1
+    }
+
+    /// The index of the trainer the player is standing next to, if any
+    fn touched_trainer(&self) -> Option<usize> {
+        if self.wall_collision {
+            None
+        } else {
+            self.trainers.iter().position(|npc| player::check_collision(&self.player_box, &npc.rect()))
+        }
+    }
+
+    /// Stops the player and has a trainer turn to them and speak; trainers who have not been beaten battle after
+    fn talk_to<'a>(&mut self, game: &Game<'a>, trainer_index: usize) -> Result<Transition<'a>, String> {
+        self.body.stop();
+        self.trainers[trainer_index].face_towards(&self.player_box);
+
+        let id = self.trainers[trainer_index].id.clone();
+        let dialogue = if game.defeated_trainers.contains(&id) {
+            DialogueScene::new(game, &format!("{}_defeated", id), "trainer_defeated", &[])?
+        } else {
+            let gym_no = self.gym_no;
+            DialogueScene::new(game, &id, "trainer", &[])?.then(move |game, _| trainer_battle(game, gym_no, trainer_index))
+        };
+        Ok(Transition::Push(Box::new(dialogue)))
+    }
+
+    /// Stops the player and has the leader speak before the battle
+    fn talk_to_leader<'a>(&mut self, game: &Game<'a>) -> Result<Transition<'a>, String> {
+        self.body.stop();
+        let gym = &game.gyms[self.gym_no];
+        let gym_no = self.gym_no;
+        let dialogue = DialogueScene::new(game, &gym.leader_profile, "leader", &[("badge", &gym.badge)])?;
+        Ok(Transition::Push(Box::new(dialogue.then(move |game, _| leader_battle(game, gym_no)))))
+    }
+}
+
+/// Sets up a battle against one of the trainers in the maze of a gym
+fn trainer_battle<'a>(game: &mut Game<'a>, gym_no: usize, trainer_index: usize) -> Transition<'a> {
+    game.trainer = Some(trainer_id(gym_no, trainer_index));
+    let profile = ai::trainer_profile(game.ai_profiles, game.trainer_profiles, &format!("gym_trainer_{}", gym_no));
+
+    // Each trainer in the maze uses one of the gym's designed teams
+    let gyms = game.gyms;
+    let gym = &gyms[gym_no];
+    let movesets = game.gym_movesets;
+    let (enemy_team, enemy_movesets) = if gym.trainer_teams.is_empty() {
+        let themed = monster::theme_monsters(game.monsters, &gym.theme);
+        (monster::select_random_team(&themed, 3, monster::experience(game.difficulty, game.player_badges.len())), None)
+    } else {
+        let team_no = trainer_index % gym.trainer_teams.len();
+        (gym_team(&gym.trainer_teams[team_no]), Some(&movesets[gym_no].0[team_no]))
+    };
+    game.start_battle(enemy_team, &monster::BattleType::GymTrainer, enemy_movesets, profile, None)
+}
+
+/// Sets up the battle against the leader of a gym
+fn leader_battle<'a>(game: &mut Game<'a>, gym_no: usize) -> Transition<'a> {
+    let gyms = game.gyms;
+    let gym = &gyms[gym_no];
+    let profile = game
+        .ai_profiles
+        .get(&gym.leader_profile)
+        .cloned()
+        .unwrap_or_else(ai::AiProfile::balanced);
+
+    let movesets = game.gym_movesets;
+    let (enemy_team, enemy_movesets) = if gym.leader_team.is_empty() {
+        let themed = monster::theme_monsters(game.monsters, &gym.theme);
+        (monster::select_random_team(&themed, 4, monster::experience(game.difficulty, game.player_badges.len())), None)
+    } else {
+        (gym_team(&gym.leader_team), Some(&movesets[gym_no].1))
+    };
+    let victory_message = Some(format!("You earned the {}!", gym.badge));
+    game.start_battle(enemy_team, &monster::BattleType::GymLeader, enemy_movesets, profile, victory_message)
+}
+
+impl<'a> Scene<'a> for GymScene {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        if self.challenge.is_some() {
+            self.direction = (0, 0);
+            return Ok(Transition::None);
+        }
+        self.direction = player::movement_input(input);
+
+        if input.was_pressed(Action::Menu) {
+            return Ok(Transition::Push(Box::new(menu::TeamMenuScene::new())));
+        }
+        if input.was_pressed(Action::Map) {
+            self.show_map = !self.show_map;
+        }
+
+        // Check if the player wants to exit the gym
+        let leaving = input.was_pressed(Action::Interact) || input.was_pressed(Action::Cancel);
+        if leaving && player::check_collision(&self.player_box, &self.exit_box(game)) {
+            self.body.stop();
+            let leaving = Rc::clone(&self.leaving);
+            let dialogue = DialogueScene::new(game, "leave_gym", "leave_gym", &[])?.then(move |_, answer| {
+                leaving.set(answer == Some(dialogue::YES));
+                Transition::None
+            });
+            return Ok(Transition::Push(Box::new(dialogue)));
+        }
+
+        if input.was_pressed(Action::Interact) {
+            if let Some(trainer_index) = self.touched_trainer() {
+                return self.talk_to(game, trainer_index);
+            } else if player::check_collision(&self.player_box, &self.leader(game)) {
+                return self.talk_to_leader(game);
+            }
+        }
+
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String> {
+        // Leave once the player has said so; the maze is different next time
+        if self.leaving.get() {
+            reload_maze(game, self.gym_no);
+            return Ok(Transition::Pop);
+        }
+
+        self.leader_sprite.advance(dt);
+
+        // A trainer who spotted the player walks up to them, then speaks before the battle
+        if let Some(challenge) = self.challenge.as_mut() {
+            let trainer = challenge.trainer;
+            self.player_sprite.walk((0, 0), dt);
+            if challenge.advance(&mut self.trainers[trainer], &self.player_box, dt) {
+                self.challenge = None;
+                return self.talk_to(game, trainer);
+            }
+            return Ok(Transition::None);
+        }
+
+        let before = self.player_box;
+        self.body.step(self.direction, dt);
+        self.player_box.reposition((self.body.x(), self.body.y()));
+        self.player_sprite.walk(self.direction, dt);
+
+        // Prevent the player from going thru walls, using the "go-back" approach
+        let maze = &game.gym_mazes[self.gym_no];
+        let layout = game.layouts.get(self.gym_no);
+        let mut walls = gym_walls(maze, layout);
+        walls.extend(puzzle_walls(maze, layout, &self.puzzles, before));
+        self.wall_collision = walls.iter().any(|w| player::check_collision(&self.player_box, w));
+        if self.wall_collision {
+            self.body.go_back();
+            self.player_box.reposition((self.body.x(), self.body.y()));
+        }
+
+        let transition = self.step_on_puzzles(game)?;
+        if !matches!(transition, Transition::None) {
+            return Ok(transition);
+        }
+
+        // Remember where the player has been for the map
+        let layout = game.layouts.get(self.gym_no);
+        if let Some((row, column)) = cell_at(&game.gym_mazes[self.gym_no], layout, self.player_box.center()) {
+            game.gym_explored[self.gym_no][row][column] = true;
+        }
+
+        // Trainers look around the maze, and come over to battle once they spot the player with no wall between them
+        let player_box = self.player_box;
+        let behind_wall = |r: &Rect| walls.iter().any(|w| r.has_intersection(*w));
+        for npc in self.trainers.iter_mut() {
+            npc.update(dt, |r| behind_wall(r) || r.has_intersection(player_box));
+        }
+        if game.last_battle.elapsed().as_secs_f64() > overworld::ENCOUNTER_GRACE {
+            let spotted = self
+                .trainers
+                .iter()
+                .position(|npc| !game.defeated_trainers.contains(&npc.id) && npc.spots(&player_box, behind_wall));
+            if let Some(trainer) = spotted {
+                self.body.stop();
+                self.challenge = Some(Challenge::new(trainer));
+                return Ok(Transition::None);
+            }
+        }
+
+        // Stop next to trainers who have not been beaten and the leader so the player can choose to battle them
+        let unbeaten = self.touched_trainer().is_some_and(|trainer| !game.defeated_trainers.contains(&self.trainers[trainer].id));
+        if unbeaten || player::check_collision(&self.player_box, &self.leader(game)) {
+            self.body.stop();
+        }
+
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, alpha: f64) -> Result<(), String> {
+        // The camera follows the player through the maze
+        let (draw_x, draw_y) = self.body.drawn_at(alpha);
+        let drawn_player = Rect::new(draw_x, draw_y, PLAYER_SIZE as u32, PLAYER_SIZE as u32);
+        let maze = &game.gym_mazes[self.gym_no];
+        let layout = game.layouts.get(self.gym_no);
+        let (width, height) = gym_size(maze, layout);
+        let camera = Camera::following(drawn_player, width, height);
+        draw_gym(wincan, &game.assets, &camera, maze, layout, &self.puzzles)?;
+        for npc in self.trainers.iter() {
+            npc.draw(wincan, &game.assets, game.sprites, camera.to_screen(npc.drawn_at(alpha)))?;
+        }
+        let leader = self.leader(game);
+        self.leader_sprite.draw(wincan, &game.assets, game.sprites, camera.to_screen(leader))?;
+        self.player_sprite.draw(wincan, &game.assets, game.sprites, camera.to_screen(drawn_player))?;
+        if let Some(challenge) = &self.challenge {
+            let trainer = camera.to_screen(self.trainers[challenge.trainer].drawn_at(alpha));
+            challenge.draw(wincan, &game.assets, trainer)?;
+        }
+        if self.show_map {
+            draw_minimap(wincan, game, self.gym_no, drawn_player, &self.trainers, leader)?;
+        }
+
+        // Say what Interact does where the player is standing
+        let hint = if player::check_collision(&self.player_box, &self.exit_box(game)) {
+            Some("Leave")
+        } else if self.touched_trainer().is_some() || player::check_collision(&self.player_box, &leader) {
+            Some("Talk")
+        } else {
+            None
+        };
+        if let (true, Some(hint)) = (self.challenge.is_none(), hint) {
+            ui::draw_hint(wincan, &game.assets, &format!("{}: {}", game.bindings.describe(Action::Interact), hint))?;
+        }
+
+        Ok(())
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Gym
+    }
+}
+
+/// The badge case, shown over the overworld
+pub struct BadgeCaseScene;
+
+impl<'a> Scene<'a> for BadgeCaseScene {
+    fn handle_input(&mut self, _game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        if input.was_pressed(Action::Confirm) || input.was_pressed(Action::Cancel) || input.was_pressed(Action::Badges) {
+            Ok(Transition::Pop)
+        } else {
+            Ok(Transition::None)
+        }
+    }
+
+    fn update(&mut self, _game: &mut Game<'a>, _dt: f64) -> Result<Transition<'a>, String> {
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        draw_badge_case(wincan, &game.assets, &game.gyms[..BADGE_COUNT], &game.player_badges)
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::BadgeCase
     }
 }
@@ -0,0 +1,144 @@
+use std::fs;
+
+use rand::RngCore;
+
+use crate::maze::{self, Maze, MazeGenerator, Puzzle};
+use crate::parse::parse_number;
+
+pub const LAYOUT_FILE: &str = "./data/gym_layouts.txt";
+
+/// The difficulty the sizes in the layout file are for; easier and harder ones shrink and grow the mazes
+pub const DEFAULT_DIFFICULTY: usize = 1;
+
+/// How the maze of a gym is built and drawn
+pub struct GymLayout {
+    /// The rows and columns of the maze on the default difficulty, when the player first gets to the gym
+    pub rows: usize,
+    pub columns: usize,
+    /// The rows and columns added for each level of difficulty above the default, and for each badge
+    /// earned since first getting to the gym; they are taken away for each level below the default
+    pub grow_rows: usize,
+    pub grow_columns: usize,
+    /// The width and height in pixels of a cell of the maze
+    pub cell_width: u32,
+    pub cell_height: u32,
+    /// The colors of the floor and the walls, as red, green and blue
+    pub floor: (u8, u8, u8),
+    pub wall: (u8, u8, u8),
+    /// How likely a dead end of the maze is to have a trainer waiting in it
+    pub trainer_chance: f64,
+    pub generator: &'static dyn MazeGenerator,
+    /// The doors, switches, gates and teleport pads in the maze
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl GymLayout {
+    /// The rows and columns of the maze at the given difficulty, once the player has earned the given
+    /// number of badges since first getting to the gym; a maze always keeps at least one row and column
+    pub fn dimensions(&self, difficulty: usize, badges: usize) -> (usize, usize) {
+        let level = difficulty as isize - DEFAULT_DIFFICULTY as isize + badges as isize;
+        let grow = |size: usize, by: usize| (size as isize + by as isize * level).max(1) as usize;
+        (grow(self.rows, self.grow_rows), grow(self.columns, self.grow_columns))
+    }
+
+    /// Makes a maze for the gym
+    ///
+    /// * `badges` - The badges earned since first getting to the gym, which are those of the gym and the ones after it
+    pub fn maze(&self, difficulty: usize, badges: usize, rng: &mut dyn RngCore) -> Maze {
+        let (rows, columns) = self.dimensions(difficulty, badges);
+        let mut gym_maze = maze::gym_maze(rows, columns, self.generator, self.trainer_chance, rng);
+        maze::add_puzzles(&mut gym_maze, &self.puzzles, rng);
+        gym_maze
+    }
+}
+
+/// The layout of every gym's maze, loaded from `data/gym_layouts.txt`, in gym order
+///
+/// The file has one `gym,rows,columns,cell_width,cell_height,floor,wall,trainer_chance,generator,grow_rows,grow_columns,puzzles`
+/// record per line after a header line. Colors are written in hex, like `5789A2`, and the
+/// generator is one of the names in `maze::GENERATORS`. Puzzles are a `;` separated list of
+/// `door`, `switch`, `gate` and `teleport`, or `none`.
+pub struct GymLayouts {
+    layouts: Vec<GymLayout>,
+}
+
+fn parse_color(s: &str, line_number: usize) -> Result<(u8, u8, u8), String> {
+    let s = s.trim();
+    let channel = |i: usize| s.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+    match (s.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(red), Some(green), Some(blue)) => Ok((red, green, blue)),
+        _ => Err(format!("line {}: {} is not a color", line_number, s)),
+    }
+}
+
+fn parse_puzzles(s: &str, line_number: usize) -> Result<Vec<Puzzle>, String> {
+    match s.trim() {
+        "none" => Ok(Vec::new()),
+        s => s
+            .split(';')
+            .map(|name| Puzzle::from_name(name.trim()).ok_or_else(|| format!("line {}: there is no {} puzzle", line_number, name)))
+            .collect(),
+    }
+}
+
+impl GymLayouts {
+    pub fn load(path: &str) -> Result<GymLayouts, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        GymLayouts::parse(&contents).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn parse(contents: &str) -> Result<GymLayouts, String> {
+        let mut layouts: Vec<GymLayout> = Vec::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            match v[..] {
+                [""] => {}
+                [gym, rows, columns, cell_width, cell_height, floor, wall, trainer_chance, generator, grow_rows, grow_columns, puzzles] => {
+                    if parse_number::<usize>(gym, line_number)? != layouts.len() {
+                        return Err(format!("line {}: expected the layout of gym {}", line_number, layouts.len()));
+                    }
+                    let layout = GymLayout {
+                        rows: parse_number(rows, line_number)?,
+                        columns: parse_number(columns, line_number)?,
+                        grow_rows: parse_number(grow_rows, line_number)?,
+                        grow_columns: parse_number(grow_columns, line_number)?,
+                        cell_width: parse_number(cell_width, line_number)?,
+                        cell_height: parse_number(cell_height, line_number)?,
+                        floor: parse_color(floor, line_number)?,
+                        wall: parse_color(wall, line_number)?,
+                        trainer_chance: parse_number(trainer_chance, line_number)?,
+                        generator: maze::generator(generator.trim())
+                            .ok_or_else(|| format!("line {}: there is no {} maze generator", line_number, generator))?,
+                        puzzles: parse_puzzles(puzzles, line_number)?,
+                    };
+                    if layout.rows == 0 || layout.columns == 0 {
+                        return Err(format!("line {}: a maze needs at least one row and column", line_number));
+                    }
+                    if !(0.0..=1.0).contains(&layout.trainer_chance) {
+                        return Err(format!("line {}: {} is not a chance between 0 and 1", line_number, trainer_chance));
+                    }
+                    layouts.push(layout);
+                }
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        if layouts.is_empty() {
+            return Err("there are no gym layouts".to_string());
+        }
+        Ok(GymLayouts { layouts })
+    }
+
+    /// How many gyms have a layout
+    pub fn count(&self) -> usize {
+        self.layouts.len()
+    }
+
+    /// The layout of a gym; gyms past the last layout share it
+    pub fn get(&self, gym_no: usize) -> &GymLayout {
+        &self.layouts[gym_no.min(self.layouts.len() - 1)]
+    }
+}
@@ -0,0 +1,360 @@
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::GameControllerSubsystem;
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::time::{Duration, Instant};
+
+// The bindings the game ships with; every action has to be bound here
+const BINDINGS_FILE: &str = "./data/keybindings.txt";
+
+// The player's own bindings, saved from the options screen over the ones in `BINDINGS_FILE`
+const USER_BINDINGS_FILE: &str = "./data/keybindings.user.txt";
+
+// How far a stick has to be pushed before it counts as a direction
+const STICK_DEADZONE: i16 = 16000;
+
+// Seconds a direction is held in a menu before it starts repeating, and then between repeats
+const REPEAT_DELAY: f64 = 0.4;
+const REPEAT_INTERVAL: f64 = 0.1;
+
+// Only the directions repeat, so holding Confirm does not pick things over and over
+const REPEATING: [Action; 4] = [Action::Up, Action::Down, Action::Left, Action::Right];
+
+/// Something the player can do, independent of the key or button that does it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel,
+    Menu,
+    Interact,
+    Badges,
+    Journal,
+    Options,
+    Map,
+}
+
+pub const ACTIONS: [Action; 12] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Confirm,
+    Action::Cancel,
+    Action::Menu,
+    Action::Interact,
+    Action::Badges,
+    Action::Journal,
+    Action::Options,
+    Action::Map,
+];
+
+impl Action {
+    /// The name of the action in the bindings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Menu => "menu",
+            Action::Interact => "interact",
+            Action::Badges => "badges",
+            Action::Journal => "journal",
+            Action::Options => "options",
+            Action::Map => "map",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        ACTIONS.iter().copied().find(|a| a.name() == name)
+    }
+}
+
+fn remove_user_bindings() -> Result<(), String> {
+    match fs::remove_file(USER_BINDINGS_FILE) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(format!("Cannot remove {}: {}", USER_BINDINGS_FILE, e)),
+        _ => Ok(()),
+    }
+}
+
+/// The keys and controller buttons bound to every action; loaded from `data/keybindings.txt`,
+/// then from `data/keybindings.user.txt` where the options screen saves the player's changes
+///
+/// Each line of the files is `action,key;key,button;button`, using SDL's names for keys
+/// and game controller buttons.
+#[derive(Clone, PartialEq)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<Keycode>>,
+    buttons: HashMap<Action, Vec<Button>>,
+}
+
+impl Bindings {
+    /// The bindings the game ships with, before the player changes any
+    fn shipped() -> Result<Bindings, String> {
+        let file = File::open(BINDINGS_FILE).map_err(|e| format!("Cannot open {}: {}", BINDINGS_FILE, e))?;
+        let mut bindings = Bindings {
+            keys: HashMap::new(),
+            buttons: HashMap::new(),
+        };
+        bindings.read_file(file);
+        if let Some(action) = ACTIONS.iter().find(|a| !bindings.keys.contains_key(a)) {
+            return Err(format!("{}: {} has no binding", BINDINGS_FILE, action.name()));
+        }
+        Ok(bindings)
+    }
+
+    /// Loads the bindings files, keeping the shipped bindings for anything the player has not changed
+    pub fn load() -> Result<Bindings, String> {
+        let mut bindings = Bindings::shipped()?;
+        if let Ok(file) = File::open(USER_BINDINGS_FILE) {
+            bindings.read_file(file);
+        }
+        Ok(bindings)
+    }
+
+    /// Forgets the player's changes, going back to the bindings the game ships with
+    pub fn restore_defaults() -> Result<Bindings, String> {
+        remove_user_bindings()?;
+        Bindings::shipped()
+    }
+
+    fn read_file(&mut self, file: File) {
+        // Lines that cannot be read are skipped, like lines naming an unknown action
+        for line in BufReader::new(file).lines().skip(1).filter_map(Result::ok) {
+            self.parse_line(&line);
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        let v = line.split(',').collect::<Vec<&str>>();
+        let action = match Action::from_name(v[0]) {
+            Some(action) => action,
+            None => return,
+        };
+        if let Some(keys) = v.get(1) {
+            self.keys.insert(action, keys.split(';').filter_map(Keycode::from_name).collect());
+        }
+        if let Some(buttons) = v.get(2) {
+            self.buttons.insert(action, buttons.split(';').filter_map(Button::from_string).collect());
+        }
+    }
+
+    /// Writes the bindings to the player's bindings file, or removes it if they are the shipped ones
+    pub fn save(&self) -> Result<(), String> {
+        if *self == Bindings::shipped()? {
+            return remove_user_bindings();
+        }
+        let mut contents = String::from("action,keys,buttons\n");
+        for action in ACTIONS.iter() {
+            let keys: Vec<String> = self.keys(*action).iter().map(|k| k.name()).collect();
+            let buttons: Vec<String> = self.buttons(*action).iter().map(|b| b.string()).collect();
+            contents += &format!("{},{},{}\n", action.name(), keys.join(";"), buttons.join(";"));
+        }
+        fs::write(USER_BINDINGS_FILE, contents).map_err(|e| format!("Cannot write {}: {}", USER_BINDINGS_FILE, e))
+    }
+
+    pub fn keys(&self, action: Action) -> &[Keycode] {
+        self.keys.get(&action).map_or(&[], |k| k.as_slice())
+    }
+
+    pub fn buttons(&self, action: Action) -> &[Button] {
+        self.buttons.get(&action).map_or(&[], |b| b.as_slice())
+    }
+
+    /// Makes `key` the only key for the action, taking it away from any other action
+    pub fn bind_key(&mut self, action: Action, key: Keycode) {
+        for keys in self.keys.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.keys.insert(action, vec![key]);
+    }
+
+    /// Makes `button` the only controller button for the action, taking it away from any other action
+    pub fn bind_button(&mut self, action: Action, button: Button) {
+        for buttons in self.buttons.values_mut() {
+            buttons.retain(|b| *b != button);
+        }
+        self.buttons.insert(action, vec![button]);
+    }
+
+    /// Lists the keys and buttons bound to an action, for showing on screen
+    pub fn describe(&self, action: Action) -> String {
+        let mut names: Vec<String> = self.keys(action).iter().map(|k| k.name()).collect();
+        names.extend(self.buttons(action).iter().map(|b| format!("Pad {}", b.string())));
+        if names.is_empty() {
+            String::from("(unbound)")
+        } else {
+            names.join(" / ")
+        }
+    }
+
+    /// The actions bound to any of the keys
+    pub fn key_actions(&self, keys: &HashSet<Keycode>) -> HashSet<Action> {
+        ACTIONS
+            .iter()
+            .copied()
+            .filter(|a| self.keys(*a).iter().any(|k| keys.contains(k)))
+            .collect()
+    }
+
+    /// The actions bound to any of the buttons
+    pub fn button_actions(&self, buttons: &HashSet<Button>) -> HashSet<Action> {
+        ACTIONS
+            .iter()
+            .copied()
+            .filter(|a| self.buttons(*a).iter().any(|b| buttons.contains(b)))
+            .collect()
+    }
+}
+
+/// The input of a single frame
+pub struct Input {
+    pub held: HashSet<Action>,
+    pub pressed: HashSet<Action>,
+    /// The actions pressed this frame, along with directions held long enough to repeat
+    pub repeated: HashSet<Action>,
+    /// The first key pressed this frame, used for rebinding
+    pub key: Option<Keycode>,
+    /// The first controller button pressed this frame, used for rebinding
+    pub button: Option<Button>,
+}
+
+impl Input {
+    /// Whether the action is currently held down
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// Whether the action was started this frame
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether the action was started this frame, or has been held long enough to repeat;
+    /// used for moving around menus
+    pub fn was_repeated(&self, action: Action) -> bool {
+        self.repeated.contains(&action)
+    }
+}
+
+/// Repeats held directions after a delay, like a held key does when typing
+///
+/// Repeats are timed with the clock rather than by counting frames, so menus scroll at the
+/// same speed no matter the frame rate.
+pub struct KeyRepeat {
+    next: HashMap<Action, Instant>,
+}
+
+impl KeyRepeat {
+    pub fn new() -> KeyRepeat {
+        KeyRepeat { next: HashMap::new() }
+    }
+
+    /// Returns the actions pressed this frame along with the directions that repeat this frame
+    ///
+    /// * `held` - The actions held this frame
+    /// * `pressed` - The actions started this frame
+    /// * `now` - The time of this frame
+    pub fn update(&mut self, held: &HashSet<Action>, pressed: &HashSet<Action>, now: Instant) -> HashSet<Action> {
+        let mut repeated = pressed.clone();
+        self.next.retain(|action, _| held.contains(action));
+
+        for action in REPEATING.iter() {
+            if pressed.contains(action) || (held.contains(action) && !self.next.contains_key(action)) {
+                self.next.insert(*action, now + Duration::from_secs_f64(REPEAT_DELAY));
+            } else if let Some(next) = self.next.get_mut(action) {
+                if now >= *next {
+                    repeated.insert(*action);
+                    *next = now + Duration::from_secs_f64(REPEAT_INTERVAL);
+                }
+            }
+        }
+        repeated
+    }
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        KeyRepeat::new()
+    }
+}
+
+/// The game controllers that are plugged in
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    pads: Vec<GameController>,
+}
+
+impl Controllers {
+    /// Opens every controller that is already plugged in
+    pub fn new(subsystem: GameControllerSubsystem) -> Controllers {
+        let mut controllers = Controllers {
+            subsystem,
+            pads: Vec::new(),
+        };
+        let count = controllers.subsystem.num_joysticks().unwrap_or(0);
+        for index in 0..count {
+            controllers.open(index);
+        }
+        controllers
+    }
+
+    fn open(&mut self, index: u32) {
+        if !self.subsystem.is_game_controller(index) {
+            return;
+        }
+        if let Ok(pad) = self.subsystem.open(index) {
+            if self.pads.iter().all(|p| p.instance_id() != pad.instance_id()) {
+                self.pads.push(pad);
+            }
+        }
+    }
+
+    /// Opens and closes controllers as they are plugged in and out
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => self.open(*which),
+            Event::ControllerDeviceRemoved { which, .. } => self.pads.retain(|p| p.instance_id() != *which),
+            _ => {}
+        }
+    }
+
+    /// The actions held on any controller, counting the left stick as the directions
+    pub fn held(&self, bindings: &Bindings) -> HashSet<Action> {
+        let mut buttons = HashSet::new();
+        for pad in self.pads.iter() {
+            for action in ACTIONS.iter() {
+                buttons.extend(bindings.buttons(*action).iter().copied().filter(|b| pad.button(*b)));
+            }
+        }
+        let mut held = bindings.button_actions(&buttons);
+
+        for pad in self.pads.iter() {
+            let x = pad.axis(Axis::LeftX);
+            let y = pad.axis(Axis::LeftY);
+            if x < -STICK_DEADZONE {
+                held.insert(Action::Left);
+            }
+            if x > STICK_DEADZONE {
+                held.insert(Action::Right);
+            }
+            if y < -STICK_DEADZONE {
+                held.insert(Action::Up);
+            }
+            if y > STICK_DEADZONE {
+                held.insert(Action::Down);
+            }
+        }
+        held
+    }
+}
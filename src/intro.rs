@@ -1,5 +1,23 @@
 use sdl2::rect::Rect;
 use sdl2::pixels::Color;
+use sdl2::render::WindowCanvas;
+
+use std::time::Instant;
+
+use crate::assets::TextureId;
+use crate::camera::{CAM_W, CAM_H};
+use crate::gym;
+use crate::menu;
+use crate::overworld;
+use crate::input::{Action, Input};
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::ui;
+
+// How long the title screen is shown, in seconds
+const WELCOME_TIME: f64 = 3.5;
+
+// How long each fade out of the intro takes, in seconds
+const FADE_TIME: f64 = 1.0;
 
 pub fn draw_intro( 
     wincan: &mut sdl2::render::WindowCanvas,
@@ -18,7 +36,98 @@ pub fn draw_intro(
     wincan.fill_rect(left_rect)?;
     wincan.fill_rect(right_rect)?;
 
-    wincan.present();
-    
     Ok(())
+}
+
+/// The title screen followed by the difficulty selection
+pub struct IntroScene {
+    welcome: TextureId,
+    difficulty: TextureId,
+    start: Instant,
+    welcome_done: bool,
+    choice: usize,
+    confirmed: bool,
+    fade: Option<ui::Fade>,
+}
+
+impl IntroScene {
+    pub fn new(game: &Game) -> IntroScene {
+        IntroScene {
+            welcome: game.assets.id("images/welcome.png"),
+            difficulty: game.assets.id("images/difficulty_select.png"),
+            start: Instant::now(),
+            welcome_done: false,
+            choice: 1,
+            confirmed: false,
+            fade: None,
+        }
+    }
+}
+
+impl<'a> Scene<'a> for IntroScene {
+    fn handle_input(&mut self, _game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        if !self.welcome_done || self.fade.is_some() {
+            return Ok(Transition::None);
+        }
+        if input.was_repeated(Action::Up) {
+            self.choice = (self.choice + 2) % 3;
+        }
+        if input.was_repeated(Action::Down) {
+            self.choice = (self.choice + 1) % 3;
+        }
+        if input.was_pressed(Action::Confirm) {
+            self.confirmed = true;
+        }
+        if input.was_pressed(Action::Options) {
+            return Ok(Transition::Push(Box::new(menu::OptionsScene::new())));
+        }
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String> {
+        // Fade out of the title screen once it has been shown long enough,
+        // and out of the difficulty selection once a difficulty is picked
+        let welcome_over = !self.welcome_done && self.start.elapsed().as_secs_f64() >= WELCOME_TIME;
+        if self.fade.is_none() && (welcome_over || self.confirmed) {
+            self.fade = Some(ui::Fade::new(FADE_TIME));
+        }
+
+        let faded = match self.fade.as_mut() {
+            Some(fade) => fade.advance(dt),
+            None => false,
+        };
+        if !faded {
+            return Ok(Transition::None);
+        }
+        self.fade = None;
+
+        if !self.welcome_done {
+            self.welcome_done = true;
+            return Ok(Transition::None);
+        }
+        game.difficulty = self.choice;
+        // Gym mazes grow with the difficulty
+        gym::reload_mazes(game);
+        let town = overworld::OverworldScene::at_spawn(game, "start");
+        Ok(Transition::Replace(Box::new(town)))
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        let screen = Rect::new(0, 0, CAM_W, CAM_H);
+        if self.welcome_done {
+            wincan.copy(game.assets.get(self.difficulty), None, screen)?;
+            draw_intro(wincan, self.choice)?;
+        } else {
+            wincan.copy(game.assets.get(self.welcome), None, screen)?;
+        }
+
+        match &self.fade {
+            Some(fade) => fade.draw(wincan),
+            None => Ok(()),
+        }
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Intro
+    }
 }
\ No newline at end of file
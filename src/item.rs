@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::parse::parse_number;
+
+pub const ITEM_FILE: &str = "./data/items.txt";
+
+/// Something the player can carry, and what it costs at the shop
+pub struct Item {
+    pub id: String,
+    pub name: String,
+    pub price: u32,
+    /// How many badges the player needs before the shop stocks it
+    pub badges: usize,
+    pub description: String,
+}
+
+impl Item {
+    /// What the shop pays for one the player sells
+    pub fn sell_price(&self) -> u32 {
+        self.price / 2
+    }
+}
+
+/// Every item, loaded from `data/items.txt`, in the order the shop lists them
+///
+/// The file has one `id,name,price,badges,description` record per line after a header line.
+/// The shop only stocks an item once the player has earned `badges` badges, and buys items
+/// back for half their price. The description can have commas.
+pub struct Items {
+    items: Vec<Item>,
+}
+
+impl Items {
+    pub fn load(path: &str) -> Result<Items, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        Items::parse(&contents).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn parse(contents: &str) -> Result<Items, String> {
+        let mut items: Vec<Item> = Vec::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().splitn(5, ',').collect::<Vec<&str>>();
+            match v[..] {
+                [""] => {}
+                [id, name, price, badges, description] => {
+                    if items.iter().any(|i| i.id == id) {
+                        return Err(format!("line {}: there is already an item called {}", line_number, id));
+                    }
+                    items.push(Item {
+                        id: id.to_string(),
+                        name: name.to_string(),
+                        price: parse_number(price, line_number)?,
+                        badges: parse_number(badges, line_number)?,
+                        description: description.to_string(),
+                    });
+                }
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        Ok(Items { items })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Item> {
+        self.items.iter().find(|i| i.id == id)
+    }
+
+    /// The items the shop sells to a player with the given number of badges
+    pub fn stock(&self, badges: usize) -> Vec<&Item> {
+        self.items.iter().filter(|i| i.badges <= badges).collect()
+    }
+
+    /// The items the player is carrying, in the order the shop lists them
+    pub fn carried(&self, carrying: &HashMap<String, u32>) -> Vec<&Item> {
+        self.items.iter().filter(|i| carrying.get(&i.id).is_some_and(|c| *c > 0)).collect()
+    }
+}
@@ -1,5 +1,56 @@
-use rand::{self, Rng};
-use std::collections::HashMap;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use std::fmt;
+
+use crate::maze_analysis;
+
+// How likely Eller's algorithm is to leave a wall between neighbors it could join
+const ELLERS_WALL_CHANCE: f64 = 0.3;
+
+/// A side of a cell of a maze
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// The side of a cell that faces a cell next to it, both given as (row, column)
+    pub fn between(from: (usize, usize), to: (usize, usize)) -> Side {
+        if to.0 < from.0 {
+            Side::Top
+        } else if to.0 > from.0 {
+            Side::Bottom
+        } else if to.1 < from.1 {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+}
+
+/// Something in a cell of a gym maze besides its walls
+///
+/// Doors, switch walls and gates stand in a passage of the maze; the walls of the cell
+/// leave that side open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Feature {
+    None,
+    /// A key, which unlocks the door with the same number
+    Key(usize),
+    /// A door in a side of the cell, locked until the player has the key with the same number
+    Door(Side, usize),
+    /// A floor switch, which opens and closes the switch wall with the same number each time it is stepped on
+    Switch(usize),
+    /// A wall in a side of the cell, which stands while the switch with the same number is off
+    SwitchWall(Side, usize),
+    /// A gate in a side of the cell, which only lets the player through on the way out of the cell
+    Gate(Side),
+    /// A pad that sends the player to another cell, given as (row, column)
+    Teleport((usize, usize)),
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Container {
@@ -9,6 +60,7 @@ pub struct Container {
     pub top_wall: bool,
     pub set: usize,
     pub let_spawn: bool,
+    pub feature: Feature,
 }
 
 impl Container {
@@ -18,13 +70,14 @@ impl Container {
         return v.len() == 3;
     }
 
-    pub fn assign_spawn(&mut self) -> bool {
-        let ran = rand::thread_rng().gen_range(0..7);
-        if ran == 2 {
-            self.let_spawn = true;
-        } else {
-            self.let_spawn = false;
-        }
+    /// Whether a trainer waits in the cell: at a dead end, if one may
+    pub fn has_trainer(&self) -> bool {
+        self.determine_corner() && self.let_spawn
+    }
+
+    /// Decides at random whether a trainer may wait in the cell, with the given chance
+    pub fn assign_spawn(&mut self, chance: f64, rng: &mut dyn RngCore) -> bool {
+        self.let_spawn = rng.gen_bool(chance);
         return self.let_spawn;
     }
 }
@@ -36,223 +89,502 @@ pub struct Maze {
     pub maze_height: usize,
 }
 
-impl Maze {
-    // This function create the layout of all the walls in the maze, called before gameloop
-    pub fn create_random_maze(height: usize, width: usize) -> Maze {
-        // Create the maze
-        let mut maze: Vec<Vec<Container>> = Vec::new();
-        //let mut spawn_board = Hashmap::new();
-        // Create the first row. No containers will be members of any set
-        let mut current_row: Vec<Container> = Vec::new();
-        for _n in 0..width {
-            current_row.push(Container {
-                bottom_wall: false,
-                right_wall: false,
-                top_wall: false,
-                left_wall: false,
-                set: 0,
-                let_spawn: false,
-            });
+/// Draws the maze in text, for debugging and tests
+///
+/// Corners are `+`, walls are `--` and `|`, and the right and bottom walls of each cell are
+/// drawn, along with the top walls of the first row and the left walls of the first column.
+/// Cells with a key, switch or teleport pad are marked `k`, `s` and `t`, and cells with a door,
+/// switch wall or gate in one of their sides are marked `D`, `W` and `G`.
+impl fmt::Display for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut top = String::from("+");
+        for container in self.maze[0].iter() {
+            top += if container.top_wall { "--+" } else { "  +" };
         }
-        // Create the hashmap for the sets
-        let mut map: HashMap<usize, Vec<usize>> = HashMap::new();
-        // Iterate for each row of the specified height
-        let mut current_height = 0;
-        let last_height = height - 1;
-        let last_width = width - 1;
-        while current_height < height {
-            // Ellers algorithm only views the current row and has no memory of previously processed rows.
-            if map.is_empty() == false {
-                map.drain(); //clears map for each row
+        writeln!(f, "{}", top)?;
+
+        for row in self.maze.iter() {
+            let mut cells = String::from(if row[0].left_wall { "|" } else { " " });
+            let mut bottom = String::from("+");
+            for container in row.iter() {
+                cells += match container.feature {
+                    Feature::None => "  ",
+                    Feature::Key(_) => " k",
+                    Feature::Door(..) => " D",
+                    Feature::Switch(_) => " s",
+                    Feature::SwitchWall(..) => " W",
+                    Feature::Gate(_) => " G",
+                    Feature::Teleport(_) => " t",
+                };
+                cells += if container.right_wall { "|" } else { " " };
+                bottom += if container.bottom_wall { "--+" } else { "  +" };
             }
-            // Iterate through all set members and add to hashmap
-            let mut current_index = 0;
-            for current_container in current_row.iter() {
-                // If set number is not present in hashmap, insert.
-                if map.is_empty() || !map.contains_key(&current_container.set) {
-                    let mut index_vector = Vec::new();
-                    index_vector.insert(0, current_index);
-                    map.insert(current_container.set, index_vector);
-                } else {
-                    //Otherwise, add to values of specified key.
-                    let mut current_vector_at_key =
-                        map.get(&current_container.set).unwrap().clone();
-                    map.remove(&current_container.set);
-                    current_vector_at_key.insert(current_vector_at_key.len(), current_index);
-                    map.insert(current_container.set, current_vector_at_key.to_vec());
-                }
-                current_index += 1;
+            writeln!(f, "{}", cells)?;
+            writeln!(f, "{}", bottom)?;
+        }
+        Ok(())
+    }
+}
+
+/// The cells of a maze being generated, and the passages opened between them so far
+pub struct Grid {
+    height: usize,
+    width: usize,
+    /// Whether each cell is open to the cell on its right
+    open_right: Vec<Vec<bool>>,
+    /// Whether each cell is open to the cell below it
+    open_down: Vec<Vec<bool>>,
+}
+
+impl Grid {
+    /// A grid with every wall standing
+    pub fn new(height: usize, width: usize) -> Grid {
+        Grid {
+            height,
+            width,
+            open_right: vec![vec![false; width]; height],
+            open_down: vec![vec![false; width]; height],
+        }
+    }
+
+    /// Knocks down the wall between two cells next to each other, given as (row, column)
+    pub fn open(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let ((row, column), other) = if a < b { (a, b) } else { (b, a) };
+        if other == (row, column + 1) {
+            self.open_right[row][column] = true;
+        } else if other == (row + 1, column) {
+            self.open_down[row][column] = true;
+        } else {
+            panic!("Cells {:?} and {:?} are not next to each other", a, b);
+        }
+    }
+
+    /// The cells above, below, left and right of a cell that are in the grid
+    pub fn neighbors(&self, (row, column): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut neighbors = Vec::new();
+        if row > 0 {
+            neighbors.push((row - 1, column));
+        }
+        if row + 1 < self.height {
+            neighbors.push((row + 1, column));
+        }
+        if column > 0 {
+            neighbors.push((row, column - 1));
+        }
+        if column + 1 < self.width {
+            neighbors.push((row, column + 1));
+        }
+        neighbors
+    }
+
+    /// The maze with walls wherever no passage was opened, and all around the outside
+    ///
+    /// Each wall is kept by the cells on both sides of it, so that `right_wall` of one cell
+    /// always agrees with `left_wall` of the next.
+    pub fn into_maze(self) -> Maze {
+        let mut maze: Vec<Vec<Container>> = Vec::new();
+        for row in 0..self.height {
+            let mut current_row = Vec::new();
+            for column in 0..self.width {
+                current_row.push(Container {
+                    left_wall: column == 0 || !self.open_right[row][column - 1],
+                    right_wall: !self.open_right[row][column],
+                    top_wall: row == 0 || !self.open_down[row - 1][column],
+                    bottom_wall: !self.open_down[row][column],
+                    set: 0,
+                    let_spawn: false,
+                    feature: Feature::None,
+                });
             }
-            //Join any containers not members of a set to their own unique set
-            // If hashmap of sets contains non-members, remove from hashmap and insert into another vector.
-            if map.contains_key(&0) {
-                let mut non_members = map.get(&0).unwrap().clone();
-                map.remove(&0).unwrap();
-                // While the non-members vector has entries in it, iterate through and assign an index to each entry and
-                // insert in next available mapping in hashmap
-                let current_container = 0;
-                while non_members.is_empty() == false {
-                    //Assign new mapping
-                    let mut grouping = 1;
-                    while current_row[non_members[current_container]].set == 0 {
-                        if map.contains_key(&grouping) == false {
-                            current_row[non_members[current_container]].set = grouping;
-                            let mut vec_to_insert = Vec::new();
-                            vec_to_insert.insert(0, non_members[current_container]);
-                            map.insert(grouping, vec_to_insert);
-                        } else {
-                            grouping += 1;
-                        }
-                    }
-                    // removes the entry from the non-members vector
-                    non_members.remove(current_container);
-                }
+            maze.push(current_row);
+        }
+        Maze {
+            maze,
+            maze_width: self.width,
+            maze_height: self.height,
+        }
+    }
+}
+
+/// A way of building the walls of a maze
+///
+/// Every generator makes a perfect maze, where there is exactly one path between any two cells.
+pub trait MazeGenerator {
+    /// The name gym data picks the generator by
+    fn name(&self) -> &'static str;
+
+    /// Makes a maze with the given number of rows and columns
+    fn generate(&self, height: usize, width: usize, rng: &mut dyn RngCore) -> Maze;
+}
+
+/// Eller's algorithm: builds the maze a row at a time, keeping track of which cells of the
+/// row are already joined through the rows above
+pub struct Ellers;
+
+impl MazeGenerator for Ellers {
+    fn name(&self) -> &'static str {
+        "ellers"
+    }
+
+    fn generate(&self, height: usize, width: usize, rng: &mut dyn RngCore) -> Maze {
+        let mut grid = Grid::new(height, width);
+        // The set each cell of the current row is in; cells of the same set are joined somehow
+        let mut sets: Vec<usize> = vec![0; width];
+        let mut next_set = 1;
+
+        for row in 0..height {
+            // Cells not joined to the row above start out in a set of their own
+            for set in sets.iter_mut().filter(|s| **s == 0) {
+                *set = next_set;
+                next_set += 1;
             }
 
-            //Create right-walls, moving from left to right:
-            let mut current_container = 0;
-            while current_container < width - 1 {
-                // If in same set, then create a wall betweem them
-                if current_row[current_container].set != current_row[current_container + 1].set {
-                    let mut rng = rand::thread_rng();
-                    let n1: u8 = rng.gen_range(0..10);
-                    if n1 == 3 || n1 == 8 || n1 == 5 {
-                        current_row[current_container].right_wall = true;
-                    } else {
-                        //otherwise, union the current container and container to the right
-                        let mut union_component_one = map
-                            .get(&current_row[current_container].set)
-                            .unwrap()
-                            .clone();
-                        let union_component_two = map
-                            .get(&current_row[current_container + 1].set)
-                            .unwrap()
-                            .clone();
-                        map.remove(&current_row[current_container].set);
-                        map.remove(&current_row[current_container + 1].set);
-                        let union_component_set_value: usize = current_row[current_container].set;
-                        union_component_one.extend(union_component_two);
-                        for n in 0..union_component_one.len() {
-                            current_row[union_component_one[n]].set = union_component_set_value;
-                        }
-                        map.insert(union_component_set_value, union_component_one.to_vec());
+            // Join neighbors that are not joined yet, at random except on the last row, which joins all of them
+            let last_row = row + 1 == height;
+            for column in 0..width - 1 {
+                if sets[column] != sets[column + 1] && (last_row || !rng.gen_bool(ELLERS_WALL_CHANCE)) {
+                    grid.open((row, column), (row, column + 1));
+                    let (kept, merged) = (sets[column], sets[column + 1]);
+                    for set in sets.iter_mut().filter(|s| **s == merged) {
+                        *set = kept;
                     }
-                } else {
-                    current_row[current_container].right_wall = true;
-                    current_container += 1;
-                    continue;
                 }
-                current_container += 1;
+            }
+            if last_row {
+                break;
             }
 
-            // Create bottom-walls, moving from left to right: Randomly decide to add a wall or not.
-            // Make sure that each set has at least one container without a bottom-wall
-            let mut mapping_clone = map.clone();
-            let mut index_of_container = 0;
-            let _v = current_row.clone();
-            for container in &mut current_row {
-                let mut rng = rand::thread_rng();
-                let n1: u8 = rng.gen_range(0..10);
-                if n1 == 3 || n1 == 8 || n1 == 5 {
-                    let set = mapping_clone.get_mut(&container.set).unwrap();
-                    //If a container is the only member of its set, do not create a bottom-wall
-                    if set.len() == 1 {
-                        container.bottom_wall = false;
-                    } else {
-                        let mut element = 0;
-                        while element < set.len() {
-                            if set[element] == index_of_container {
-                                set.remove(element);
-                                continue;
-                            }
-                            element += 1;
-                        }
-                        container.bottom_wall = true;
-                    }
+            // Every set goes on into the next row through at least one of its cells
+            let mut below: Vec<usize> = vec![0; width];
+            for column in 0..width {
+                if !rng.gen_bool(ELLERS_WALL_CHANCE) {
+                    grid.open((row, column), (row + 1, column));
+                    below[column] = sets[column];
                 }
-                index_of_container += 1;
             }
+            for column in 0..width {
+                let set = sets[column];
+                if !below.contains(&set) {
+                    let members: Vec<usize> = (0..width).filter(|c| sets[*c] == set).collect();
+                    let down = *members.choose(rng).unwrap();
+                    grid.open((row, down), (row + 1, down));
+                    below[down] = set;
+                }
+            }
+            sets = below;
+        }
 
-            // Add top wall to every element in first row
-            if current_height == 0 {
-                let mut iterate = 0;
-                while iterate < current_row.len() {
-                    current_row[iterate].top_wall = true;
-                    iterate += 1;
+        grid.into_maze()
+    }
+}
+
+/// The recursive backtracker: wanders from cell to cell without crossing its own path,
+/// backing up whenever it is stuck; makes long, winding passages
+pub struct RecursiveBacktracker;
+
+impl MazeGenerator for RecursiveBacktracker {
+    fn name(&self) -> &'static str {
+        "recursive_backtracker"
+    }
+
+    fn generate(&self, height: usize, width: usize, rng: &mut dyn RngCore) -> Maze {
+        let mut grid = Grid::new(height, width);
+        let mut visited = vec![vec![false; width]; height];
+        let start = (rng.gen_range(0..height), rng.gen_range(0..width));
+        visited[start.0][start.1] = true;
+        let mut path = vec![start];
+
+        while let Some(&cell) = path.last() {
+            let unvisited: Vec<(usize, usize)> = grid.neighbors(cell).into_iter().filter(|(r, c)| !visited[*r][*c]).collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    grid.open(cell, next);
+                    visited[next.0][next.1] = true;
+                    path.push(next);
+                }
+                None => {
+                    path.pop();
                 }
             }
-            current_row[0].left_wall = true;
-            // Keep adding rows until desired height reached
-            if current_height < last_height {
-                //output row
-                let row_clone = current_row.clone();
-                maze.insert(maze.len(), row_clone);
-                //remove right walls, containers with bottom wall from set, remove all bottom walls
-                for current_container in current_row.iter_mut() {
-                    current_container.right_wall = false;
-                    if current_container.bottom_wall {
-                        current_container.set = 0;
-                        current_container.bottom_wall = false;
-                    }
+        }
+
+        grid.into_maze()
+    }
+}
+
+/// Prim's algorithm: grows the maze out from one cell, joining a random cell next to it each
+/// time; makes many short dead ends
+pub struct Prims;
+
+impl MazeGenerator for Prims {
+    fn name(&self) -> &'static str {
+        "prims"
+    }
+
+    fn generate(&self, height: usize, width: usize, rng: &mut dyn RngCore) -> Maze {
+        let mut grid = Grid::new(height, width);
+        let mut in_maze = vec![vec![false; width]; height];
+        // Cells next to the maze that are not part of it yet
+        let mut frontier: Vec<(usize, usize)> = Vec::new();
+
+        let start = (rng.gen_range(0..height), rng.gen_range(0..width));
+        let mut next = Some(start);
+        while let Some(cell) = next {
+            in_maze[cell.0][cell.1] = true;
+            for neighbor in grid.neighbors(cell) {
+                if !in_maze[neighbor.0][neighbor.1] && !frontier.contains(&neighbor) {
+                    frontier.push(neighbor);
                 }
-                current_row[last_width].right_wall = true;
             }
-            current_height += 1;
+
+            next = None;
+            if !frontier.is_empty() {
+                let cell = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+                let joined: Vec<(usize, usize)> = grid.neighbors(cell).into_iter().filter(|(r, c)| in_maze[*r][*c]).collect();
+                grid.open(cell, *joined.choose(rng).unwrap());
+                next = Some(cell);
+            }
         }
 
-        // Add bottom wall to every container in the row
-        let mut current_container = 0;
-        while current_container < current_row.len() - 1 {
-            current_row[current_container].bottom_wall = true;
-            // if adjacent cell have members in different cell , remove right wall and union sets
-            if current_row[current_container].set == current_row[current_container + 1].set {
-                current_container += 1;
-                continue;
-            } else {
-                current_row[current_container].right_wall = false;
-                let mut union_component_one = map
-                    .get(&current_row[current_container].set)
-                    .unwrap()
-                    .clone();
-                let union_component_two = map
-                    .get(&current_row[current_container + 1].set)
-                    .unwrap()
-                    .clone();
-                map.remove(&current_row[current_container].set);
-                map.remove(&current_row[current_container + 1].set);
-                let union_component_set_value: usize = current_row[current_container].set;
-                union_component_one.extend(union_component_two);
-                for n in 0..union_component_one.len() {
-                    current_row[union_component_one[n]].set = union_component_set_value;
+        grid.into_maze()
+    }
+}
+
+/// Kruskal's algorithm: knocks down walls in a random order, as long as the cells on either
+/// side are not joined yet
+pub struct Kruskals;
+
+impl MazeGenerator for Kruskals {
+    fn name(&self) -> &'static str {
+        "kruskals"
+    }
+
+    fn generate(&self, height: usize, width: usize, rng: &mut dyn RngCore) -> Maze {
+        let mut grid = Grid::new(height, width);
+        let mut walls: Vec<((usize, usize), (usize, usize))> = Vec::new();
+        for row in 0..height {
+            for column in 0..width {
+                if column + 1 < width {
+                    walls.push(((row, column), (row, column + 1)));
+                }
+                if row + 1 < height {
+                    walls.push(((row, column), (row + 1, column)));
                 }
-                map.insert(union_component_set_value, union_component_one.to_vec());
             }
-            current_container += 1;
         }
+        walls.shuffle(rng);
+
+        // Each cell points towards another cell of its set, up to the one the set is known by
+        let mut parents: Vec<usize> = (0..height * width).collect();
+        fn find(parents: &mut [usize], mut cell: usize) -> usize {
+            while parents[cell] != cell {
+                parents[cell] = parents[parents[cell]];
+                cell = parents[cell];
+            }
+            cell
+        }
+        for (a, b) in walls {
+            let set_a = find(&mut parents, a.0 * width + a.1);
+            let set_b = find(&mut parents, b.0 * width + b.1);
+            if set_a != set_b {
+                parents[set_b] = set_a;
+                grid.open(a, b);
+            }
+        }
+
+        grid.into_maze()
+    }
+}
+
+/// The binary tree algorithm: opens each cell up or to the right, so that every passage
+/// leads towards the top right corner; quick to solve from there
+pub struct BinaryTree;
 
-        current_row[last_width].bottom_wall = true;
-        // output final row
-        maze.insert(maze.len(), current_row);
+impl MazeGenerator for BinaryTree {
+    fn name(&self) -> &'static str {
+        "binary_tree"
+    }
 
-        // start to initialize all the containers randomly for if they can spawn NPCs
+    fn generate(&self, height: usize, width: usize, rng: &mut dyn RngCore) -> Maze {
+        let mut grid = Grid::new(height, width);
         for row in 0..height {
             for column in 0..width {
-                maze[row][column].assign_spawn();
+                let mut ways = Vec::new();
+                if row > 0 {
+                    ways.push((row - 1, column));
+                }
+                if column + 1 < width {
+                    ways.push((row, column + 1));
+                }
+                if let Some(&next) = ways.choose(rng) {
+                    grid.open((row, column), next);
+                }
+            }
+        }
+        grid.into_maze()
+    }
+}
+
+/// Every maze generator, by the name gym data picks it by
+pub const GENERATORS: [&dyn MazeGenerator; 5] = [&Ellers, &RecursiveBacktracker, &Prims, &Kruskals, &BinaryTree];
+
+/// The maze generator with the given name
+pub fn generator(name: &str) -> Option<&'static dyn MazeGenerator> {
+    GENERATORS.iter().find(|g| g.name() == name).copied()
+}
+
+/// The cell the player walks into a gym's maze through, by the start sign, as (row, column)
+pub fn entrance_cell(maze: &Maze) -> (usize, usize) {
+    (0, maze.maze_width - 1)
+}
+
+/// The cell the gym leader stands in, at the bottom left, as (row, column)
+pub fn boss_cell(maze: &Maze) -> (usize, usize) {
+    (maze.maze_height - 1, 0)
+}
+
+/// Makes the maze of a gym, deciding with the given chance whether a trainer may wait in each cell
+pub fn gym_maze(height: usize, width: usize, generator: &dyn MazeGenerator, trainer_chance: f64, rng: &mut dyn RngCore) -> Maze {
+    let mut maze = generator.generate(height, width, rng);
+    for container in maze.maze.iter_mut().flatten() {
+        container.assign_spawn(trainer_chance, rng);
+    }
+    // Gyms are entered through the right side of the top right cell, by the start sign
+    let (row, column) = entrance_cell(&maze);
+    maze.maze[row][column].right_wall = false;
+    maze
+}
+
+/// The puzzles a gym maze can have, besides its trainers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Puzzle {
+    /// A locked door, and a key to it
+    Door,
+    /// A switch wall, and the switch that opens it
+    Switch,
+    /// A one-way gate, which always comes with a teleport pad past it
+    Gate,
+    /// A teleport pad back to the entrance
+    Teleport,
+}
+
+impl Puzzle {
+    /// The puzzle with the given name, as gym layouts write it
+    pub fn from_name(name: &str) -> Option<Puzzle> {
+        match name {
+            "door" => Some(Puzzle::Door),
+            "switch" => Some(Puzzle::Switch),
+            "gate" => Some(Puzzle::Gate),
+            "teleport" => Some(Puzzle::Teleport),
+            _ => None,
+        }
+    }
+}
+
+/// A passage between two cells next to each other, both given as (row, column)
+type Passage = ((usize, usize), (usize, usize));
+
+/// The cells reachable from a cell without going through the given passages
+fn region(maze: &Maze, from: (usize, usize), barred: &[Passage]) -> Vec<(usize, usize)> {
+    let mut seen = vec![from];
+    let mut stack = vec![from];
+    while let Some(cell) = stack.pop() {
+        for next in maze_analysis::passages(maze, cell) {
+            let crossing = barred.iter().any(|&(a, b)| (a, b) == (cell, next) || (b, a) == (cell, next));
+            if !crossing && !seen.contains(&next) {
+                seen.push(next);
+                stack.push(next);
             }
         }
-        return Maze {
-            maze: maze,
-            maze_height: height,
-            maze_width: width,
-        };
     }
+    seen
 }
 
-pub fn reload_maze(gym_mazes: &mut Vec<Maze>, gym_no: usize) {
-    match gym_no {
-        0 => { gym_mazes[0] = Maze::create_random_maze(16, 9); }
-        1 => { gym_mazes[1] = Maze::create_random_maze(9, 6); }
-        2 => { gym_mazes[2] = Maze::create_random_maze(20, 16); }
-        _ => { gym_mazes[3] = Maze::create_random_maze(15, 15); }
+/// A cell in a region to put a key, switch or pad in: off the way to the leader if there is
+/// one, preferring dead ends, and never where something else already is
+fn free_cell(maze: &Maze, cells: &[(usize, usize)], path: &[(usize, usize)], rng: &mut dyn RngCore) -> Option<(usize, usize)> {
+    let free: Vec<(usize, usize)> = cells
+        .iter()
+        .copied()
+        .filter(|&(row, column)| {
+            let container = &maze.maze[row][column];
+            container.feature == Feature::None
+                && !container.has_trainer()
+                && (row, column) != entrance_cell(maze)
+                && (row, column) != boss_cell(maze)
+        })
+        .collect();
+    let off_path: Vec<(usize, usize)> = free.iter().copied().filter(|cell| !path.contains(cell)).collect();
+    let dead_ends: Vec<(usize, usize)> = off_path.iter().copied().filter(|&(row, column)| maze.maze[row][column].determine_corner()).collect();
+    [dead_ends, off_path, free].iter().find(|cells| !cells.is_empty()).and_then(|cells| cells.choose(rng).copied())
+}
+
+/// Adds puzzles to a gym maze, across the way from the entrance to the leader
+///
+/// Gates, then doors, then switch walls are spread out along the way. The key or switch for
+/// each is put somewhere between it and the one before, so the player always comes to it
+/// first, and gates only open towards the leader. A teleport pad goes past all of them and
+/// sends the player back to the entrance, so they can always leave.
+pub fn add_puzzles(maze: &mut Maze, puzzles: &[Puzzle], rng: &mut dyn RngCore) {
+    let entrance = entrance_cell(maze);
+    let path = match maze_analysis::shortest_path(maze, entrance, boss_cell(maze)) {
+        Some(path) => path,
+        None => return,
+    };
+    let barriers: Vec<Puzzle> = [Puzzle::Gate, Puzzle::Door, Puzzle::Switch]
+        .iter()
+        .copied()
+        .filter(|p| puzzles.contains(p))
+        .collect();
+
+    // The passages barred so far, and where along the way the part of the maze past them starts
+    let mut barred: Vec<Passage> = Vec::new();
+    let mut start = 0;
+    let mut gate = None;
+    for (number, puzzle) in barriers.iter().enumerate() {
+        // The barrier goes in the passage from path[step] to path[step + 1]
+        let step = (number + 1) * (path.len() - 1) / (barriers.len() + 1);
+        if step <= start || step + 1 >= path.len() {
+            continue;
+        }
+        let (from, to) = (path[step], path[step + 1]);
+        if maze.maze[from.0][from.1].feature != Feature::None {
+            continue;
+        }
+        barred.push((from, to));
+        let side = Side::between(from, to);
+        if *puzzle != Puzzle::Gate {
+            let (item, barrier) = match puzzle {
+                Puzzle::Door => (Feature::Key(number), Feature::Door(side, number)),
+                _ => (Feature::Switch(number), Feature::SwitchWall(side, number)),
+            };
+            maze.maze[from.0][from.1].feature = barrier;
+            let cells = region(maze, path[start], &barred);
+            match free_cell(maze, &cells, &path, rng) {
+                Some(cell) => maze.maze[cell.0][cell.1].feature = item,
+                None => {
+                    maze.maze[from.0][from.1].feature = Feature::None;
+                    barred.pop();
+                    continue;
+                }
+            }
+        } else {
+            maze.maze[from.0][from.1].feature = Feature::Gate(side);
+            gate = Some(from);
+        }
+        start = step + 1;
+    }
+
+    if puzzles.contains(&Puzzle::Teleport) || gate.is_some() {
+        let cells = region(maze, path[start], &barred);
+        match free_cell(maze, &cells, &path, rng).filter(|cell| !path.contains(cell)) {
+            Some(cell) => maze.maze[cell.0][cell.1].feature = Feature::Teleport(entrance),
+            // Without a way back, a gate could shut the player in
+            None => {
+                if let Some((row, column)) = gate {
+                    maze.maze[row][column].feature = Feature::None;
+                }
+            }
+        }
     }
-}
\ No newline at end of file
+}
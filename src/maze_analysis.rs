@@ -0,0 +1,437 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::maze::{self, Feature, Maze, Side};
+
+/// A cell of a maze, as (row, column)
+pub type Cell = (usize, usize);
+
+/// The cells next to a cell that the player can walk to from it
+///
+/// A wall on either side stops the player, so a passage needs both cells to have no wall
+/// between them.
+pub fn passages(maze: &Maze, (row, column): Cell) -> Vec<Cell> {
+    let container = &maze.maze[row][column];
+    let mut passages = Vec::new();
+    if row > 0 && !container.top_wall && !maze.maze[row - 1][column].bottom_wall {
+        passages.push((row - 1, column));
+    }
+    if row + 1 < maze.maze_height && !container.bottom_wall && !maze.maze[row + 1][column].top_wall {
+        passages.push((row + 1, column));
+    }
+    if column > 0 && !container.left_wall && !maze.maze[row][column - 1].right_wall {
+        passages.push((row, column - 1));
+    }
+    if column + 1 < maze.maze_width && !container.right_wall && !maze.maze[row][column + 1].left_wall {
+        passages.push((row, column + 1));
+    }
+    passages
+}
+
+/// The pairs of cells next to each other where one has a wall between them and the other does not
+pub fn wall_mismatches(maze: &Maze) -> Vec<(Cell, Cell)> {
+    let mut mismatches = Vec::new();
+    for row in 0..maze.maze_height {
+        for column in 0..maze.maze_width {
+            let container = &maze.maze[row][column];
+            if column + 1 < maze.maze_width && container.right_wall != maze.maze[row][column + 1].left_wall {
+                mismatches.push(((row, column), (row, column + 1)));
+            }
+            if row + 1 < maze.maze_height && container.bottom_wall != maze.maze[row + 1][column].top_wall {
+                mismatches.push(((row, column), (row + 1, column)));
+            }
+        }
+    }
+    mismatches
+}
+
+/// How many steps it takes to walk from a cell to every other, or `None` for cells it cannot reach
+pub fn distances(maze: &Maze, from: Cell) -> Vec<Vec<Option<usize>>> {
+    let mut distances = vec![vec![None; maze.maze_width]; maze.maze_height];
+    distances[from.0][from.1] = Some(0);
+    let mut queue = VecDeque::from(vec![from]);
+    while let Some(cell) = queue.pop_front() {
+        let steps = distances[cell.0][cell.1].unwrap_or(0);
+        for (row, column) in passages(maze, cell) {
+            if distances[row][column].is_none() {
+                distances[row][column] = Some(steps + 1);
+                queue.push_back((row, column));
+            }
+        }
+    }
+    distances
+}
+
+/// The cells that cannot be reached from a cell
+pub fn unreachable_cells(maze: &Maze, from: Cell) -> Vec<Cell> {
+    let distances = distances(maze, from);
+    let mut cells = Vec::new();
+    for (row, steps) in distances.iter().enumerate() {
+        for (column, steps) in steps.iter().enumerate() {
+            if steps.is_none() {
+                cells.push((row, column));
+            }
+        }
+    }
+    cells
+}
+
+/// Whether every cell can be reached from every other
+pub fn is_connected(maze: &Maze) -> bool {
+    unreachable_cells(maze, (0, 0)).is_empty()
+}
+
+/// The cells along the shortest walk between two cells, both included, if there is one
+pub fn shortest_path(maze: &Maze, from: Cell, to: Cell) -> Option<Vec<Cell>> {
+    // Walk back from the end, always to a cell one step closer to the start
+    let distances = distances(maze, from);
+    let mut steps = distances[to.0][to.1]?;
+    let mut path = vec![to];
+    let mut cell = to;
+    while steps > 0 {
+        steps -= 1;
+        cell = passages(maze, cell).into_iter().find(|&(row, column)| distances[row][column] == Some(steps))?;
+        path.push(cell);
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// How many cells have only one way out
+pub fn dead_ends(maze: &Maze) -> usize {
+    let mut count = 0;
+    for row in 0..maze.maze_height {
+        for column in 0..maze.maze_width {
+            if passages(maze, (row, column)).len() == 1 {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// How many passages there are between cells
+pub fn passage_count(maze: &Maze) -> usize {
+    let mut count = 0;
+    for row in 0..maze.maze_height {
+        for column in 0..maze.maze_width {
+            count += passages(maze, (row, column)).len();
+        }
+    }
+    // Each passage was counted from both of its cells
+    count / 2
+}
+
+/// Whether there is exactly one path between any two cells: every cell is reachable, with no loops
+pub fn is_perfect(maze: &Maze) -> bool {
+    is_connected(maze) && passage_count(maze) + 1 == maze.maze_width * maze.maze_height
+}
+
+/// Whether a door, switch wall or gate in a side of a cell keeps the player from going through
+/// it, with the given keys and switches; `leaving` is whether they are on their way out of the cell
+fn bars(feature: Feature, side: Side, leaving: bool, opened: &HashSet<usize>) -> bool {
+    match feature {
+        Feature::Door(s, number) | Feature::SwitchWall(s, number) => s == side && !opened.contains(&number),
+        Feature::Gate(s) => s == side && !leaving,
+        _ => false,
+    }
+}
+
+/// Where the player can go from a cell in one move, with the given keys and switches: through
+/// any passage without a door, switch wall or gate in the way, and across a teleport pad
+pub fn moves(maze: &Maze, cell: Cell, opened: &HashSet<usize>) -> Vec<Cell> {
+    let feature = maze.maze[cell.0][cell.1].feature;
+    let mut moves: Vec<Cell> = passages(maze, cell)
+        .into_iter()
+        .filter(|&next| {
+            let next_feature = maze.maze[next.0][next.1].feature;
+            !bars(feature, Side::between(cell, next), true, opened)
+                && !bars(next_feature, Side::between(next, cell), false, opened)
+        })
+        .collect();
+    if let Feature::Teleport(to) = feature {
+        moves.push(to);
+    }
+    moves
+}
+
+/// The cells the player can get to from a cell with the given keys and switches
+fn reach(maze: &Maze, from: Cell, opened: &HashSet<usize>) -> Vec<Vec<bool>> {
+    let mut reached = vec![vec![false; maze.maze_width]; maze.maze_height];
+    reached[from.0][from.1] = true;
+    let mut stack = vec![from];
+    while let Some(cell) = stack.pop() {
+        for (row, column) in moves(maze, cell, opened) {
+            if !reached[row][column] {
+                reached[row][column] = true;
+                stack.push((row, column));
+            }
+        }
+    }
+    reached
+}
+
+/// The cells the player can get to from a cell, picking up every key and stepping on every
+/// switch they come across, along with the numbers of those keys and switches
+pub fn puzzle_reach(maze: &Maze, from: Cell) -> (Vec<Vec<bool>>, HashSet<usize>) {
+    let mut opened = HashSet::new();
+    loop {
+        let reached = reach(maze, from, &opened);
+        let mut found = opened.clone();
+        for (row, cells) in reached.iter().enumerate() {
+            for (column, _) in cells.iter().enumerate().filter(|(_, reached)| **reached) {
+                if let Feature::Key(number) | Feature::Switch(number) = maze.maze[row][column].feature {
+                    found.insert(number);
+                }
+            }
+        }
+        if found == opened {
+            return (reached, opened);
+        }
+        opened = found;
+    }
+}
+
+/// The cells the player can get to a cell from, with the given keys and switches
+fn reached_from(maze: &Maze, to: Cell, opened: &HashSet<usize>) -> Vec<Vec<bool>> {
+    // Walk the moves backwards from the cell
+    let mut sources: Vec<Vec<Vec<Cell>>> = vec![vec![Vec::new(); maze.maze_width]; maze.maze_height];
+    for row in 0..maze.maze_height {
+        for column in 0..maze.maze_width {
+            for (next_row, next_column) in moves(maze, (row, column), opened) {
+                sources[next_row][next_column].push((row, column));
+            }
+        }
+    }
+    let mut reached = vec![vec![false; maze.maze_width]; maze.maze_height];
+    reached[to.0][to.1] = true;
+    let mut stack = vec![to];
+    while let Some((row, column)) = stack.pop() {
+        for &(source_row, source_column) in sources[row][column].iter() {
+            if !reached[source_row][source_column] {
+                reached[source_row][source_column] = true;
+                stack.push((source_row, source_column));
+            }
+        }
+    }
+    reached
+}
+
+/// Checks that a gym maze can be played: its walls agree, it is perfect, it is closed all
+/// around except at the entrance, the leader can be reached from the entrance past its puzzles,
+/// and the player can always find their way back out
+pub fn check_gym_maze(maze: &Maze) -> Result<(), String> {
+    if let Some((a, b)) = wall_mismatches(maze).first() {
+        return Err(format!("the walls of cells {:?} and {:?} do not agree", a, b));
+    }
+    if let Some(cell) = unreachable_cells(maze, maze::entrance_cell(maze)).first() {
+        return Err(format!("cell {:?} cannot be reached from the entrance", cell));
+    }
+    if !is_perfect(maze) {
+        return Err("there is more than one path between some cells".to_string());
+    }
+    let entrance = maze::entrance_cell(maze);
+    for row in 0..maze.maze_height {
+        for column in 0..maze.maze_width {
+            let container = &maze.maze[row][column];
+            let open_outside = (row == 0 && !container.top_wall)
+                || (row + 1 == maze.maze_height && !container.bottom_wall)
+                || (column == 0 && !container.left_wall)
+                || (column + 1 == maze.maze_width && !container.right_wall && (row, column) != entrance);
+            if open_outside {
+                return Err(format!("cell {:?} is open to the outside", (row, column)));
+            }
+        }
+    }
+    if maze.maze[entrance.0][entrance.1].right_wall {
+        return Err("the entrance is walled off".to_string());
+    }
+    if shortest_path(maze, entrance, maze::boss_cell(maze)).is_none() {
+        return Err("the leader cannot be reached from the entrance".to_string());
+    }
+
+    let (reached, opened) = puzzle_reach(maze, entrance);
+    let boss = maze::boss_cell(maze);
+    if !reached[boss.0][boss.1] {
+        return Err("the leader cannot be reached past the puzzles".to_string());
+    }
+    let way_out = reached_from(maze, entrance, &opened);
+    for row in 0..maze.maze_height {
+        for column in 0..maze.maze_width {
+            if reached[row][column] && !way_out[row][column] {
+                return Err(format!("the player can be stuck in cell {:?}", (row, column)));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gym_layout::{GymLayouts, DEFAULT_DIFFICULTY, LAYOUT_FILE};
+    use crate::maze::{Feature, Grid, Puzzle, Side, GENERATORS};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // These are seeded sweeps rather than randomized property tests: every run tries the same
+    // seeds, so a failure names the seed that repeats it. This is how many each sweep tries.
+    const SEEDS: u64 = 20;
+
+    // The difficulties the player can pick
+    const DIFFICULTIES: usize = 3;
+
+    const PUZZLES: [Puzzle; 4] = [Puzzle::Door, Puzzle::Switch, Puzzle::Gate, Puzzle::Teleport];
+
+    #[test]
+    fn gym_mazes_are_playable() {
+        let layouts = GymLayouts::load(LAYOUT_FILE).unwrap();
+        for gym_no in 0..layouts.count() {
+            let layout = layouts.get(gym_no);
+            // The smallest and largest sizes the gym's maze can be, made by its own generator and
+            // every other, with every puzzle
+            let sizes = [(0, 0), (DIFFICULTIES - 1, layouts.count() - gym_no)];
+            for &(difficulty, badges) in sizes.iter() {
+                let (rows, columns) = layout.dimensions(difficulty, badges);
+                for generator in GENERATORS.iter() {
+                    for seed in 0..SEEDS / 4 {
+                        let mut rng = StdRng::seed_from_u64(seed);
+                        let mut maze = maze::gym_maze(rows, columns, *generator, layout.trainer_chance, &mut rng);
+                        maze::add_puzzles(&mut maze, &PUZZLES, &mut rng);
+                        if let Err(e) = check_gym_maze(&maze) {
+                            panic!("{} gym {} seed {}: {}\n{}", generator.name(), gym_no, seed, e, maze);
+                        }
+                    }
+                }
+            }
+            for seed in 0..SEEDS {
+                let maze = layout.maze(DEFAULT_DIFFICULTY, 0, &mut StdRng::seed_from_u64(seed));
+                if let Err(e) = check_gym_maze(&maze) {
+                    panic!("gym {} seed {}: {}\n{}", gym_no, seed, e, maze);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_mazes_are_perfect() {
+        let sizes = [(1, 1), (1, 7), (7, 1), (2, 2), (5, 8)];
+        for generator in GENERATORS.iter() {
+            for &(height, width) in sizes.iter() {
+                for seed in 0..SEEDS {
+                    let maze = generator.generate(height, width, &mut StdRng::seed_from_u64(seed));
+                    assert!(wall_mismatches(&maze).is_empty(), "{} seed {}\n{}", generator.name(), seed, maze);
+                    assert!(is_perfect(&maze), "{} seed {}\n{}", generator.name(), seed, maze);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_same_seed_makes_the_same_maze() {
+        for generator in GENERATORS.iter() {
+            let first = generator.generate(9, 6, &mut StdRng::seed_from_u64(7));
+            let second = generator.generate(9, 6, &mut StdRng::seed_from_u64(7));
+            assert_eq!(first.to_string(), second.to_string(), "{}", generator.name());
+        }
+    }
+
+    #[test]
+    fn shortest_path_follows_the_corridor() {
+        // A snake through a 3 by 3 grid: right along the top, left along the middle, right along the bottom
+        let mut grid = Grid::new(3, 3);
+        grid.open((0, 0), (0, 1));
+        grid.open((0, 1), (0, 2));
+        grid.open((0, 2), (1, 2));
+        grid.open((1, 2), (1, 1));
+        grid.open((1, 1), (1, 0));
+        grid.open((1, 0), (2, 0));
+        grid.open((2, 0), (2, 1));
+        grid.open((2, 1), (2, 2));
+        let maze = grid.into_maze();
+
+        let path = shortest_path(&maze, (0, 0), (2, 2)).unwrap();
+        assert_eq!(path.len(), 9);
+        assert_eq!(path[4], (1, 1));
+        assert_eq!(dead_ends(&maze), 2);
+        assert!(is_perfect(&maze));
+    }
+
+    #[test]
+    fn finds_problems_in_broken_mazes() {
+        let mut grid = Grid::new(2, 2);
+        grid.open((0, 0), (0, 1));
+        grid.open((0, 1), (1, 1));
+        let mut maze = grid.into_maze();
+        // The bottom left cell is walled in
+        assert_eq!(unreachable_cells(&maze, (0, 0)), vec![(1, 0)]);
+        assert!(!is_connected(&maze));
+        assert_eq!(shortest_path(&maze, (0, 0), (1, 0)), None);
+
+        // A wall only one of two cells knows about
+        maze.maze[1][0].right_wall = false;
+        assert_eq!(wall_mismatches(&maze), vec![((1, 0), (1, 1))]);
+        assert!(!is_connected(&maze));
+
+        // Once both agree the maze is whole
+        maze.maze[1][1].left_wall = false;
+        assert!(wall_mismatches(&maze).is_empty());
+        assert!(is_perfect(&maze));
+
+        // Knocking one more wall down makes a loop
+        maze.maze[0][0].bottom_wall = false;
+        maze.maze[1][0].top_wall = false;
+        assert!(is_connected(&maze));
+        assert!(!is_perfect(&maze));
+    }
+
+    #[test]
+    fn puzzles_come_with_their_keys() {
+        let layouts = GymLayouts::load(LAYOUT_FILE).unwrap();
+        for gym_no in 0..layouts.count() {
+            for seed in 0..SEEDS {
+                let maze = layouts.get(gym_no).maze(DEFAULT_DIFFICULTY, 0, &mut StdRng::seed_from_u64(seed));
+                let features: Vec<Feature> = maze.maze.iter().flatten().map(|c| c.feature).collect();
+                for feature in features.iter() {
+                    let paired = match *feature {
+                        Feature::Door(_, number) => features.contains(&Feature::Key(number)),
+                        Feature::SwitchWall(_, number) => features.contains(&Feature::Switch(number)),
+                        Feature::Gate(_) => features.iter().any(|f| matches!(f, Feature::Teleport(_))),
+                        _ => true,
+                    };
+                    assert!(paired, "gym {} seed {}: {:?} has nothing to get past it\n{}", gym_no, seed, feature, maze);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn doors_open_with_keys_and_gates_open_one_way() {
+        // A corridor of four cells from the entrance on the right, with a gate out of the
+        // entrance, then a locked door in front of the leader on the left
+        let mut grid = Grid::new(1, 4);
+        grid.open((0, 0), (0, 1));
+        grid.open((0, 1), (0, 2));
+        grid.open((0, 2), (0, 3));
+        let mut maze = grid.into_maze();
+        maze.maze[0][3].right_wall = false;
+        maze.maze[0][3].feature = Feature::Gate(Side::Left);
+        maze.maze[0][1].feature = Feature::Door(Side::Left, 0);
+
+        let (reached, opened) = puzzle_reach(&maze, (0, 3));
+        assert_eq!(reached[0], vec![false, true, true, true]);
+        assert!(opened.is_empty());
+        let (reached, _) = puzzle_reach(&maze, (0, 2));
+        assert_eq!(reached[0], vec![false, true, true, false]);
+
+        // Past the gate is the key, but nothing takes the player back
+        maze.maze[0][2].feature = Feature::Key(0);
+        let (reached, opened) = puzzle_reach(&maze, (0, 3));
+        assert_eq!(reached[0], vec![true; 4]);
+        assert!(opened.contains(&0));
+        assert_eq!(check_gym_maze(&maze), Err("the player can be stuck in cell (0, 0)".to_string()));
+
+        // A teleport pad back to the entrance lets them out
+        maze.maze[0][0].feature = Feature::Teleport((0, 3));
+        assert_eq!(check_gym_maze(&maze), Ok(()));
+    }
+}
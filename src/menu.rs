@@ -0,0 +1,233 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use crate::battle;
+use crate::input::{self, Action, Input, ACTIONS};
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::ui;
+
+// The slot of the OK button, after the six team slots
+const OK_SLOT: usize = 6;
+
+// The row of the options screen after every action, which restores the default bindings
+const DEFAULTS_ROW: usize = ACTIONS.len();
+
+// The options rows fill the panel between the title and the help text at the bottom
+const OPTIONS_TOP: i32 = 110;
+const OPTIONS_HELP_TOP: i32 = 630;
+
+// The distance from one options row to the next, at most 42 pixels, so every row fits above the help text
+const OPTIONS_PITCH: i32 = {
+    let pitch = (OPTIONS_HELP_TOP - 6 - OPTIONS_TOP) / (DEFAULTS_ROW as i32 + 1);
+    if pitch < 42 { pitch } else { 42 }
+};
+
+/// Returns the slot reached by moving up from `choice`
+fn slot_up(choice: usize, team_size: usize) -> usize {
+    match choice {
+        0 | 1 => OK_SLOT,
+        2..=5 => choice - 2,
+        _ => 2 * (team_size / 2 + team_size % 2 - 1),
+    }
+}
+
+/// Returns the slot reached by moving down from `choice`
+fn slot_down(choice: usize, team_size: usize) -> usize {
+    match choice {
+        0..=3 if team_size > choice + 2 => choice + 2,
+        0..=5 => OK_SLOT,
+        _ => 0,
+    }
+}
+
+/// Returns the slot next to `choice` in the same row; left and right both lead there, and
+/// a slot with nothing beside it stays put
+fn slot_across(choice: usize, team_size: usize) -> usize {
+    match choice {
+        0 | 2 | 4 if team_size > choice + 1 => choice + 1,
+        0 | 2 | 4 => choice,
+        1 | 3 | 5 => choice - 1,
+        _ => OK_SLOT,
+    }
+}
+
+/// The menu for reordering the team, shown over the overworld, gyms and battles
+///
+/// The first monster on the team leads in battle; closing the menu with a new lead
+/// during a battle switches that monster in.
+pub struct TeamMenuScene {
+    choice: usize,
+    selected: Option<usize>,
+}
+
+impl TeamMenuScene {
+    pub fn new() -> TeamMenuScene {
+        TeamMenuScene {
+            choice: 0,
+            selected: None,
+        }
+    }
+}
+
+impl Default for TeamMenuScene {
+    fn default() -> Self {
+        TeamMenuScene::new()
+    }
+}
+
+impl<'a> Scene<'a> for TeamMenuScene {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        let team_size = game.battle_state.player_team.len();
+
+        if input.was_repeated(Action::Up) {
+            self.choice = slot_up(self.choice, team_size);
+        }
+        if input.was_repeated(Action::Down) {
+            self.choice = slot_down(self.choice, team_size);
+        }
+        if input.was_repeated(Action::Left) || input.was_repeated(Action::Right) {
+            self.choice = slot_across(self.choice, team_size);
+        }
+
+        // Cancelling closes the menu just like the OK button
+        let closing = input.was_pressed(Action::Cancel) || input.was_pressed(Action::Menu);
+        if closing || (input.was_pressed(Action::Confirm) && self.choice == OK_SLOT) {
+            game.battle_state.player_team = battle::verify_team(&game.battle_state.player_team);
+            return Ok(Transition::Pop);
+        }
+
+        if input.was_pressed(Action::Confirm) {
+            match self.selected {
+                Some(choice) => {
+                    if choice != self.choice {
+                        game.battle_state.player_team.swap(choice, self.choice);
+                        self.selected = None;
+                    }
+                }
+                None => {
+                    self.selected = Some(self.choice);
+                }
+            }
+        }
+
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, _game: &mut Game<'a>, _dt: f64) -> Result<Transition<'a>, String> {
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        battle::draw_monster_menu(wincan, &game.assets, &game.battle_draw, &game.battle_state, self.choice, self.selected)
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::TeamMenu(self.choice)
+    }
+}
+
+/// The options screen, where every action can be rebound to another key or controller button
+pub struct OptionsScene {
+    choice: usize,
+    waiting: bool,
+}
+
+impl OptionsScene {
+    pub fn new() -> OptionsScene {
+        OptionsScene {
+            choice: 0,
+            waiting: false,
+        }
+    }
+
+    /// Draws the background of a row, highlighting the chosen one; returns the top of the row
+    fn draw_row(&self, wincan: &mut WindowCanvas, row: usize) -> Result<i32, String> {
+        let y = OPTIONS_TOP + OPTIONS_PITCH * row as i32;
+        if row == self.choice {
+            wincan.set_draw_color(Color::RGB(0xf6, 0x52, 0x41));
+            wincan.fill_rect(Rect::new(260, y - 3, 760, OPTIONS_PITCH as u32))?;
+        }
+        wincan.set_draw_color(Color::RGB(0x39, 0x7B, 0xB4));
+        wincan.fill_rect(Rect::new(263, y, 754, OPTIONS_PITCH as u32 - 6))?;
+        Ok(y)
+    }
+}
+
+impl Default for OptionsScene {
+    fn default() -> Self {
+        OptionsScene::new()
+    }
+}
+
+impl<'a> Scene<'a> for OptionsScene {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        // The next key or button pressed becomes the binding of the chosen action
+        if self.waiting {
+            if let Some(key) = input.key {
+                game.bindings.bind_key(ACTIONS[self.choice], key);
+                self.waiting = false;
+            } else if let Some(button) = input.button {
+                game.bindings.bind_button(ACTIONS[self.choice], button);
+                self.waiting = false;
+            }
+            return Ok(Transition::None);
+        }
+
+        if input.was_repeated(Action::Up) {
+            self.choice = (self.choice + DEFAULTS_ROW) % (DEFAULTS_ROW + 1);
+        }
+        if input.was_repeated(Action::Down) {
+            self.choice = (self.choice + 1) % (DEFAULTS_ROW + 1);
+        }
+        if input.was_pressed(Action::Confirm) {
+            if self.choice == DEFAULTS_ROW {
+                game.bindings = input::Bindings::restore_defaults()?;
+            } else {
+                self.waiting = true;
+            }
+        }
+        if input.was_pressed(Action::Cancel) || input.was_pressed(Action::Options) {
+            game.bindings.save()?;
+            return Ok(Transition::Pop);
+        }
+
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, _game: &mut Game<'a>, _dt: f64) -> Result<Transition<'a>, String> {
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        let text_color = Color::RGB(0xbd, 0xcd, 0xde);
+        wincan.set_draw_color(Color::RGB(0x20, 0x41, 0x6a));
+        wincan.fill_rect(Rect::new(240, 40, 800, 640))?;
+        ui::draw_text(wincan, &game.assets, "Options", text_color, Rect::new(440, 50, 400, 50))?;
+
+        for (row, action) in ACTIONS.iter().enumerate() {
+            let y = self.draw_row(wincan, row)?;
+            let binding = if self.waiting && row == self.choice {
+                String::from("Press a key or button...")
+            } else {
+                game.bindings.describe(*action)
+            };
+            ui::draw_text(wincan, &game.assets, &format!("{:?}", action), Color::BLACK, Rect::new(275, y + 3, 200, OPTIONS_PITCH as u32 - 12))?;
+            ui::draw_text(wincan, &game.assets, &binding, Color::BLACK, Rect::new(500, y + 3, 500, OPTIONS_PITCH as u32 - 12))?;
+        }
+
+        let y = self.draw_row(wincan, DEFAULTS_ROW)?;
+        ui::draw_text(wincan, &game.assets, "Restore defaults", Color::BLACK, Rect::new(275, y + 3, 730, OPTIONS_PITCH as u32 - 12))?;
+
+        let help = "Confirm to rebind, Cancel to save and go back";
+        ui::draw_text(wincan, &game.assets, help, text_color, Rect::new(340, OPTIONS_HELP_TOP, 600, 40))
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Options
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use rand::seq::SliceRandom;
+
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
@@ -12,6 +14,7 @@ pub enum BattleType {
     Trainer,
     GymTrainer,
     GymLeader,
+    Champion,
 }
 
 pub struct Monster<'a> {
@@ -42,6 +45,27 @@ pub struct BattleState<'a> {
     pub opp_defense_stages: i32,
     pub player_badges: usize,
     pub battle_type: &'a BattleType,
+    pub enemy_turns: usize,
+    pub enemy_movesets: Option<&'a HashMap<String, Vec<&'a Move>>>,
+}
+
+impl<'a> BattleState<'a> {
+    /// Returns move `index` of the enemy's lead, preferring the trainer's designed moveset
+    ///
+    /// * `monsters` - Maps strings onto their Monster objects; used when no moveset is designed
+    /// * `index` - The index of the move (0-3)
+    pub fn enemy_move<'m>(&self, monsters: &'m HashMap<String, Monster<'m>>, index: usize) -> &'m Move
+    where
+        'a: 'm,
+    {
+        let name = &self.enemy_team[0].0;
+        // Designed movesets are kept for evolved forms as well
+        let species = name.trim_start_matches('\u{200b}'); // ZERO WIDTH SPACE
+        match self.enemy_movesets.and_then(|m| m.get(species)) {
+            Some(moves) => moves[index],
+            None => monsters[name].moves[index],
+        }
+    }
 }
 
 pub fn load_moves() -> HashMap<String, Move> {
@@ -108,7 +132,7 @@ pub fn str_effectiveness(attack: &Move, defense_type: &String) -> Option<String>
     };
 }
 
-fn type_effectiveness(attack_type: &String, defense_type: &String) -> f32 {
+pub fn type_effectiveness(attack_type: &String, defense_type: &String) -> f32 {
     match attack_type.as_str() {
         "Grass" => match defense_type.as_str() {
             "Fire" => 0.5,
@@ -180,7 +204,7 @@ pub fn calculate_damage(monsters: &HashMap<String, Monster>, battle_state: &mut
             &monsters[&battle_state.enemy_team[0].0],
         )
     } else {
-        let attack = monsters[&battle_state.enemy_team[0].0].moves[move_index];
+        let attack = battle_state.enemy_move(monsters, move_index);
         calculate_opp_attack(
             battle_state,
             attack,
@@ -291,3 +315,37 @@ fn calculate_opp_attack(
 
     a
 }
+
+/// Returns the name of the first monster on the team that has not fainted
+pub fn next_available_mon(v: &[(String, f32, usize)]) -> String {
+    v.iter()
+        .find(|m| m.1 > 0.0)
+        .map(|m| m.0.clone())
+        .unwrap_or_default()
+}
+
+/// Picks `num` different monsters at full health with the given experience
+pub fn select_random_team(keys: &[String], num: usize, experience: usize) -> Vec<(String, f32, usize)> {
+    let mut rng = rand::thread_rng();
+    keys.choose_multiple(&mut rng, num)
+        .map(|s| (s.clone(), 100.0, experience))
+        .collect()
+}
+
+/// Returns the names of all unevolved monsters of the given type
+pub fn theme_monsters(monsters: &HashMap<String, Monster>, theme: &str) -> Vec<String> {
+    monsters
+        .iter()
+        .filter(|(name, m)| m.monster_type == theme && !name.starts_with('\u{200b}')) // ZERO WIDTH SPACE
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// The experience of randomly generated opponents, growing with the difficulty and badges
+pub fn experience(difficulty: usize, badges: usize) -> usize {
+    match difficulty {
+        0 => 10 * (1 + badges),
+        1 => 30 * (1 + badges),
+        _ => 50 * (1 + badges),
+    }
+}
@@ -0,0 +1,341 @@
+use rand::Rng;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use crate::assets::AssetManager;
+use crate::player;
+use crate::sprite::{Animator, Facing, Sprites};
+use crate::ui;
+
+// How fast trainers walk about on their own, in pixels per second
+const WALK_SPEED: f64 = 60.0;
+// How fast a trainer who spotted the player walks up to them, in pixels per second
+const CHALLENGE_SPEED: f64 = 150.0;
+// Seconds a trainer who stands and turns looks each way
+const TURN_TIME: f64 = 2.0;
+// Seconds a patrolling trainer waits at each point of their path
+const PATROL_WAIT: f64 = 1.0;
+// The longest a wandering trainer waits between walks, in seconds
+const WANDER_WAIT: f64 = 3.0;
+// Seconds the "!" shows before a trainer who spotted the player walks up to them
+const ALERT_TIME: f64 = 0.6;
+
+/// How a trainer moves about on their own
+#[derive(Debug, Clone, PartialEq)]
+pub enum Behavior {
+    /// Stays put, looking one way
+    Stand,
+    /// Stays put, looking each way in turn
+    Turn(Vec<Facing>),
+    /// Walks from point to point along a path, then starts over
+    Patrol(Vec<(i32, i32)>),
+    /// Walks to random spots up to this many pixels from where they started
+    Wander(u32),
+}
+
+impl Behavior {
+    /// Parses a behavior along with its arguments: `stand`, `turn` with facings like
+    /// `down;left`, `patrol` with points like `480:612;600:612`, or `wander` with a distance
+    pub fn parse(kind: &str, args: Option<&str>) -> Result<Behavior, String> {
+        match (kind, args) {
+            ("stand", None) => Ok(Behavior::Stand),
+            ("turn", Some(args)) => args
+                .split(';')
+                .map(|f| Facing::parse(f).ok_or(format!("unknown facing {}", f)))
+                .collect::<Result<Vec<Facing>, String>>()
+                .map(Behavior::Turn),
+            ("patrol", Some(args)) => args
+                .split(';')
+                .map(|p| match p.split(':').map(|n| n.parse::<i32>()).collect::<Vec<_>>()[..] {
+                    [Ok(x), Ok(y)] => Ok((x, y)),
+                    _ => Err(format!("expected x:y, found {}", p)),
+                })
+                .collect::<Result<Vec<(i32, i32)>, String>>()
+                .map(Behavior::Patrol),
+            ("wander", Some(args)) => args
+                .parse::<u32>()
+                .map(Behavior::Wander)
+                .map_err(|_| format!("{} is not a number", args)),
+            _ => Err(format!("cannot read the behavior {}", kind)),
+        }
+    }
+
+    /// Trainers who stay put are drawn standing; the rest are drawn ready to walk
+    fn sprite(&self) -> &'static str {
+        match self {
+            Behavior::Stand | Behavior::Turn(_) => "trainer",
+            Behavior::Patrol(_) | Behavior::Wander(_) => "roaming_trainer",
+        }
+    }
+}
+
+/// A trainer placed in the world, before they start moving about
+pub struct NpcSpawn {
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+    pub facing: Facing,
+    /// How many pixels ahead the trainer can spot the player from; 0 for trainers who wait to be talked to
+    pub sight: u32,
+    pub behavior: Behavior,
+}
+
+/// How far a trainer got on a walk
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Progress {
+    Moving,
+    Arrived,
+    Blocked,
+}
+
+/// A trainer moving about the world by their behavior
+pub struct Npc {
+    pub id: String,
+    x: f64,
+    y: f64,
+    prev_x: f64,
+    prev_y: f64,
+    size: u32,
+    home: (i32, i32),
+    behavior: Behavior,
+    sight: u32,
+    sprite: Animator,
+    /// Seconds left before the trainer turns or sets off again
+    wait: f64,
+    /// The point of the path or the facing the trainer is on
+    step: usize,
+    /// The spot a wandering trainer is walking to
+    target: Option<(i32, i32)>,
+}
+
+impl Npc {
+    pub fn new(spawn: &NpcSpawn, size: u32) -> Npc {
+        Npc {
+            id: spawn.id.clone(),
+            x: spawn.x as f64,
+            y: spawn.y as f64,
+            prev_x: spawn.x as f64,
+            prev_y: spawn.y as f64,
+            size,
+            home: (spawn.x, spawn.y),
+            behavior: spawn.behavior.clone(),
+            sight: spawn.sight,
+            sprite: Animator::new(spawn.behavior.sprite(), spawn.facing),
+            wait: TURN_TIME,
+            step: 0,
+            target: None,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x.round() as i32, self.y.round() as i32, self.size, self.size)
+    }
+
+    /// Where to draw the trainer, `alpha` of the way through their last update
+    pub fn drawn_at(&self, alpha: f64) -> Rect {
+        let x = self.prev_x + (self.x - self.prev_x) * alpha;
+        let y = self.prev_y + (self.y - self.prev_y) * alpha;
+        Rect::new(x.round() as i32, y.round() as i32, self.size, self.size)
+    }
+
+    /// Moves about by the trainer's behavior for `dt` seconds, never into anything `blocked` says is in the way
+    pub fn update(&mut self, dt: f64, blocked: impl Fn(&Rect) -> bool) {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
+        if self.wait > 0.0 {
+            self.wait -= dt;
+            self.sprite.walk((0, 0), dt);
+            return;
+        }
+
+        match &self.behavior {
+            Behavior::Stand => {
+                self.sprite.walk((0, 0), dt);
+            }
+            Behavior::Turn(facings) => {
+                self.step = (self.step + 1) % facings.len();
+                self.sprite.face(facings[self.step]);
+                self.wait = TURN_TIME;
+            }
+            Behavior::Patrol(path) => {
+                let (point, points) = (path[self.step], path.len());
+                match self.walk_to(point, WALK_SPEED, dt, blocked) {
+                    Progress::Moving => {}
+                    Progress::Arrived => {
+                        self.step = (self.step + 1) % points;
+                        self.wait = PATROL_WAIT;
+                    }
+                    Progress::Blocked => self.wait = PATROL_WAIT,
+                }
+            }
+            Behavior::Wander(distance) => {
+                let mut rng = rand::thread_rng();
+                let (distance, home) = (*distance as i32, self.home);
+                let (x, y) = (self.x.round() as i32, self.y.round() as i32);
+                // Wander along one line at a time, like walking on a grid
+                let target = *self.target.get_or_insert_with(|| {
+                    let offset = rng.gen_range(-distance..=distance);
+                    if rng.gen_bool(0.5) {
+                        (home.0 + offset, y)
+                    } else {
+                        (x, home.1 + offset)
+                    }
+                });
+                if self.walk_to(target, WALK_SPEED, dt, blocked) != Progress::Moving {
+                    self.target = None;
+                    self.wait = rng.gen_range(0.5..WANDER_WAIT);
+                }
+            }
+        }
+    }
+
+    /// Walks towards a point for `dt` seconds, first across and then up or down
+    fn walk_to(&mut self, point: (i32, i32), speed: f64, dt: f64, blocked: impl Fn(&Rect) -> bool) -> Progress {
+        let dx = point.0 as f64 - self.x;
+        let dy = point.1 as f64 - self.y;
+        if dx.abs() < 0.5 && dy.abs() < 0.5 {
+            self.x = point.0 as f64;
+            self.y = point.1 as f64;
+            self.sprite.walk((0, 0), dt);
+            return Progress::Arrived;
+        }
+
+        let step = speed * dt;
+        let (old_x, old_y) = (self.x, self.y);
+        let direction = if dx.abs() >= 0.5 {
+            self.x += dx.clamp(-step, step);
+            (dx.signum() as i32, 0)
+        } else {
+            self.y += dy.clamp(-step, step);
+            (0, dy.signum() as i32)
+        };
+
+        if blocked(&self.rect()) {
+            self.x = old_x;
+            self.y = old_y;
+            self.sprite.face(Facing::of(direction).unwrap());
+            self.sprite.walk((0, 0), dt);
+            return Progress::Blocked;
+        }
+        self.sprite.walk(direction, dt);
+        Progress::Moving
+    }
+
+    /// Whether the trainer can see `target` straight ahead, with nothing `blocked` says is in the way
+    pub fn spots(&self, target: &Rect, blocked: impl Fn(&Rect) -> bool) -> bool {
+        if self.sight == 0 {
+            return false;
+        }
+        let me = self.rect();
+        let facing = self.sprite.facing();
+
+        // The stretch between the trainer and the target, as wide as the trainer
+        let (start, end) = match facing {
+            Facing::Right => (me.right(), target.left()),
+            Facing::Left => (target.right(), me.left()),
+            Facing::Down => (me.bottom(), target.top()),
+            Facing::Up => (target.bottom(), me.top()),
+        };
+        let lined_up = match facing {
+            Facing::Left | Facing::Right => target.bottom() > me.top() && target.top() < me.bottom(),
+            Facing::Up | Facing::Down => target.right() > me.left() && target.left() < me.right(),
+        };
+        let gap = end - start;
+        if !lined_up || gap < 0 || gap > self.sight as i32 {
+            return false;
+        }
+        if gap == 0 {
+            return true;
+        }
+
+        let between = match facing {
+            Facing::Left | Facing::Right => Rect::new(start, me.y(), gap as u32, me.height()),
+            Facing::Up | Facing::Down => Rect::new(me.x(), start, me.width(), gap as u32),
+        };
+        !blocked(&between)
+    }
+
+    /// Stands still for `dt` seconds, whatever the trainer's behavior
+    pub fn idle(&mut self, dt: f64) {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
+        self.sprite.walk((0, 0), dt);
+    }
+
+    /// Walks straight up to `target` for `dt` seconds; returns whether the trainer is next to it
+    pub fn walk_up_to(&mut self, target: &Rect, dt: f64) -> bool {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
+        let me = self.rect();
+        if player::check_collision(&me, target) {
+            self.sprite.walk((0, 0), dt);
+            return true;
+        }
+        let point = match self.sprite.facing() {
+            Facing::Right => (target.left() - me.width() as i32, me.y()),
+            Facing::Left => (target.right(), me.y()),
+            Facing::Down => (me.x(), target.top() - me.height() as i32),
+            Facing::Up => (me.x(), target.bottom()),
+        };
+        self.walk_to(point, CHALLENGE_SPEED, dt, |_| false) == Progress::Arrived
+    }
+
+    /// Walks towards a point for `dt` seconds, through anything in the way, like in a cutscene;
+    /// returns whether the trainer is there
+    pub fn walk_towards(&mut self, point: (i32, i32), dt: f64) -> bool {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
+        self.walk_to(point, WALK_SPEED, dt, |_| false) == Progress::Arrived
+    }
+
+    /// Turns to look at `target`, like when the player talks to the trainer
+    pub fn face_towards(&mut self, target: &Rect) {
+        let me = self.rect().center();
+        let there = target.center();
+        let (dx, dy) = (there.x() - me.x(), there.y() - me.y());
+        let direction = if dx.abs() >= dy.abs() { (dx.signum(), 0) } else { (0, dy.signum()) };
+        if let Some(facing) = Facing::of(direction) {
+            self.sprite.face(facing);
+        }
+    }
+
+    pub fn draw(&self, wincan: &mut WindowCanvas, assets: &AssetManager, sprites: &Sprites, dest: Rect) -> Result<(), String> {
+        self.sprite.draw(wincan, assets, sprites, dest)
+    }
+}
+
+/// A trainer who spotted the player: a "!" over their head, then the walk up to the player
+pub struct Challenge {
+    /// The index of the trainer among those of the scene
+    pub trainer: usize,
+    alert: f64,
+}
+
+impl Challenge {
+    pub fn new(trainer: usize) -> Challenge {
+        Challenge {
+            trainer,
+            alert: ALERT_TIME,
+        }
+    }
+
+    /// Plays out the challenge for `dt` seconds; returns whether the trainer has reached the player
+    pub fn advance(&mut self, npc: &mut Npc, player: &Rect, dt: f64) -> bool {
+        if self.alert > 0.0 {
+            self.alert -= dt;
+            npc.idle(dt);
+            return false;
+        }
+        npc.walk_up_to(player, dt)
+    }
+
+    /// Draws the "!" over the trainer, given where the trainer is on screen
+    pub fn draw(&self, wincan: &mut WindowCanvas, assets: &AssetManager, trainer: Rect) -> Result<(), String> {
+        let bubble = Rect::new(trainer.center().x() - 12, trainer.top() - 34, 24, 30);
+        wincan.set_draw_color(Color::WHITE);
+        wincan.fill_rect(bubble)?;
+        ui::draw_text(wincan, assets, "!", Color::RGB(0xf6, 0x52, 0x41), bubble)
+    }
+}
@@ -1,274 +1,511 @@
 extern crate rand;
 
-use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::WindowCanvas;
 
+use crate::ai;
+use crate::camera::{self, Camera};
+use crate::dialogue::{self, DialogueScene};
+use crate::event::{EventRunner, Step};
+use crate::gym;
+use crate::menu;
+use crate::monster;
+use crate::player;
+use crate::quest;
+use crate::input::{Action, Input};
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::npc::{Challenge, Npc};
+use crate::tilemap::{TileMap, WarpTarget};
+use crate::ui;
+
 
 const TILE_SIZE: u32 = 16;
 
-const CAM_W: u32 = 1280;
-const CAM_H: u32 = 720;
+// Seconds after a battle before wild monsters can appear or trainers can spot the player again
+pub const ENCOUNTER_GRACE: f64 = 3.0;
 
+// Pixels walked that make up a step, which is when wild monsters may jump out
+const STEP_LENGTH: f64 = (TILE_SIZE * 2) as f64;
 
-// it also serve as tagging the blocks as spawnable
-pub fn draw_overworld(wincan: &mut sdl2::render::WindowCanvas) -> Result<(), String> {
-  let texture_creator = wincan.texture_creator();
+// Seconds the screen takes to fade out when a wild monster appears
+const ENCOUNTER_FADE: f64 = 0.8;
 
-  // Texture
-  let tree_sheet = texture_creator.load_texture("images/tree.png")?;
-  let grass_sheet = texture_creator.load_texture("images/grass_patch_32.png")?;
-  let water_sheet = texture_creator.load_texture("images/water_patch_32.png")?;
-  let rock_sheet = texture_creator.load_texture("images/rock_patch.png")?;
-  wincan.set_draw_color(Color::RGBA(0, 128, 128, 255));
-  wincan.clear();
+// Seconds the screen takes to fade out when walking into another area
+const AREA_FADE: f64 = 0.3;
 
-  // Draw bottom trees
-  let mut i = 0;
-  while i * TILE_SIZE < CAM_W {
-    let src = Rect::new(((i % 4) * TILE_SIZE) as i32, 0, TILE_SIZE, 4 * TILE_SIZE);
-    let pos = Rect::new(
-      (i * TILE_SIZE) as i32,
-      (CAM_H - 4 * TILE_SIZE) as i32,
-      TILE_SIZE,
-      4 * TILE_SIZE,
-    );
+// Seconds the nurse takes to heal the team, and how many times the screen flashes meanwhile
+const HEAL_TIME: f64 = 1.5;
+const HEAL_FLASHES: f64 = 3.0;
 
-    wincan.copy(&tree_sheet, src, pos)?;
 
-    i += 1;
-  }
+/// The trainers of a map
+fn map_npcs(map: &TileMap) -> Vec<Npc> {
+  map.npcs.iter().map(|spawn| Npc::new(spawn, TILE_SIZE * 2)).collect()
+}
 
-  // Draw upper trees
-  let mut i = 0;
-  while i * TILE_SIZE < CAM_W {
-    let src = Rect::new(((i % 4) * TILE_SIZE) as i32, 0, TILE_SIZE, 4 * TILE_SIZE);
-    let pos = Rect::new((i * TILE_SIZE) as i32, 0, TILE_SIZE, 4 * TILE_SIZE);
+/// Walking around the towns and routes of the world
+pub struct OverworldScene {
+  /// The area of the world the player is in
+  area: usize,
+  player: player::Player,
+  body: player::Body,
+  direction: (i32, i32),
+  npcs: Vec<Npc>,
+  /// A trainer who spotted the player and is coming over to battle
+  challenge: Option<Challenge>,
+  /// Pixels walked since the last step
+  walked: f64,
+  /// Plays while heading into a battle with a wild monster, along with the monster
+  encounter: Option<(ui::Fade, (String, f32, usize))>,
+  /// Plays while walking off the edge of the area, along with the area and position it leads to
+  leaving: Option<(ui::Fade, (usize, i32, i32))>,
+  /// The event being run, which the player waits on
+  event: Option<EventRunner>,
+  /// The trigger the player is standing in, which does not start its event again until they step out
+  trigger: Option<usize>,
+  /// The area and door the player came into the building they are in through, which its exit
+  /// leads back out of
+  door: Option<(usize, Rect)>,
+}
 
-    wincan.copy(&tree_sheet, src, pos)?;
+impl OverworldScene {
+  /// Creates the world with the player standing at the given position of an area
+  pub fn new(game: &Game, area: usize, x: i32, y: i32) -> OverworldScene {
+    OverworldScene {
+      area,
+      player: player::Player::create(Rect::new(x, y, TILE_SIZE * 2, TILE_SIZE * 2)),
+      body: player::Body::new(x, y),
+      direction: (0, 0),
+      npcs: map_npcs(game.world.map(area)),
+      challenge: None,
+      walked: 0.0,
+      encounter: None,
+      leaving: None,
+      event: None,
+      trigger: game.world.map(area).trigger_at(&Rect::new(x, y, TILE_SIZE * 2, TILE_SIZE * 2)),
+      door: None,
+    }
+  }
 
-    i += 1;
+  /// Creates the world with the player standing at the spawn point with the given name
+  pub fn at_spawn(game: &Game, name: &str) -> OverworldScene {
+    let (area, x, y) = game.world.spawn(name);
+    OverworldScene::new(game, area, x, y)
   }
 
-  // Draw grass patches to the top left corner of map
-  let mut i = 6;
-  while i * TILE_SIZE < 320 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 96, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 128, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 160, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 192, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&grass_sheet, src, pos_1)?;
-    wincan.copy(&grass_sheet, src, pos_2)?;
-    wincan.copy(&grass_sheet, src, pos_3)?;
-    wincan.copy(&grass_sheet, src, pos_4)?;
-
-    i += 1;
+  /// The map of the area the player is in
+  fn map<'m>(&self, game: &Game<'m>) -> &'m TileMap {
+    game.world.map(self.area)
   }
 
-  // Draw a pond to the right bottom corner of map
-  let mut i = 48;
-  while i * TILE_SIZE < 1060 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 480, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 512, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 544, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 576, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&water_sheet, src, pos_1)?;
-    wincan.copy(&water_sheet, src, pos_2)?;
-    wincan.copy(&water_sheet, src, pos_3)?;
-    wincan.copy(&water_sheet, src, pos_4)?;
-
-    i += 1;
+  /// Moves the player into another area, along with its trainers
+  fn enter_area(&mut self, game: &Game, area: usize, x: i32, y: i32) {
+    self.area = area;
+    self.npcs = map_npcs(game.world.map(area));
+    self.challenge = None;
+    self.stand_at(x, y);
+    self.trigger = game.world.map(area).trigger_at(&self.player_box());
   }
 
-  // Draw rock patch to right upper corner of map
-  let mut i = 60;
-  while i * TILE_SIZE < 1240 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 66, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 98, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 130, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 162, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_5 = Rect::new((i * TILE_SIZE) as i32, 194, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&rock_sheet, src, pos_1)?;
-    wincan.copy(&rock_sheet, src, pos_2)?;
-    wincan.copy(&rock_sheet, src, pos_3)?;
-    wincan.copy(&rock_sheet, src, pos_4)?;
-    wincan.copy(&rock_sheet, src, pos_5)?;
-    i += 1;
+  fn player_box(&self) -> Rect {
+    Rect::new(self.player.x(), self.player.y(), self.player.width(), self.player.height())
   }
 
-  // Draw large grass patches to north of map
-  let mut i = 32;
-  while i * TILE_SIZE < 820 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 96, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 128, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 160, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 192, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&grass_sheet, src, pos_1)?;
-    wincan.copy(&grass_sheet, src, pos_2)?;
-    wincan.copy(&grass_sheet, src, pos_3)?;
-    wincan.copy(&grass_sheet, src, pos_4)?;
-
-    i += 1;
+  /// Moves the player and stops them
+  fn stand_at(&mut self, x: i32, y: i32) {
+    self.body.place(x, y);
+    self.sync_player();
   }
 
-  // Draw grass patches to the south of map
-  let mut i = 32;
-  while i * TILE_SIZE < 730 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 480, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 512, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 544, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 576, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&grass_sheet, src, pos_1)?;
-    wincan.copy(&grass_sheet, src, pos_2)?;
-    wincan.copy(&grass_sheet, src, pos_3)?;
-    wincan.copy(&grass_sheet, src, pos_4)?;
-
-    i += 1;
+  /// Moves the player's collision box to where their body is
+  fn sync_player(&mut self) {
+    self.player.set_x(self.body.x());
+    self.player.set_y(self.body.y());
   }
 
-  // Draw grass patches to the center right of map
-  let mut i = 62;
-  while i * TILE_SIZE < 1240 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 300, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 332, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 364, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 396, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&grass_sheet, src, pos_1)?;
-    wincan.copy(&grass_sheet, src, pos_2)?;
-    wincan.copy(&grass_sheet, src, pos_3)?;
-    wincan.copy(&grass_sheet, src, pos_4)?;
-
-    i += 1;
+  /// The index of the trainer the player is standing next to, if any
+  fn touched_trainer(&self) -> Option<usize> {
+    let player_box = self.player_box();
+    self.npcs.iter().position(|npc| player::check_collision(&player_box, &npc.rect()))
   }
 
-  // Draw pond to the middle center left of map
-  let mut i = 4;
-  while i * TILE_SIZE < 300 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 280, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 312, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 344, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 376, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&water_sheet, src, pos_1)?;
-    wincan.copy(&water_sheet, src, pos_2)?;
-    wincan.copy(&water_sheet, src, pos_3)?;
-    wincan.copy(&water_sheet, src, pos_4)?;
-
-    i += 1;
+  /// Stops the player and has a trainer turn to them and speak; trainers who have not been beaten battle after
+  ///
+  /// Trainers with an event of their own run it instead.
+  fn talk_to<'a>(&mut self, game: &Game<'a>, trainer: usize) -> Result<Transition<'a>, String> {
+    let player_box = self.player_box();
+    self.stand_at(player_box.x(), player_box.y());
+    self.npcs[trainer].face_towards(&player_box);
+
+    let id = self.npcs[trainer].id.clone();
+    if let Some(event) = self.map(game).talk_events.get(&id) {
+      self.event = Some(EventRunner::new(game.events, event));
+      return Ok(Transition::None);
+    }
+    let dialogue = if game.defeated_trainers.contains(&id) {
+      DialogueScene::new(game, &format!("{}_defeated", id), "trainer_defeated", &[])?
+    } else {
+      DialogueScene::new(game, &id, "trainer", &[])?.then(move |game, _| trainer_battle(game, id))
+    };
+    Ok(Transition::Push(Box::new(dialogue)))
   }
 
-  // Draw small rock patch in middle of map
-  let mut i = 24;
-  while i * TILE_SIZE < 570 {
-    let src = Rect::new(((i % 2) * TILE_SIZE) as i32, 0, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_1 = Rect::new((i * TILE_SIZE) as i32, 280, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_2 = Rect::new((i * TILE_SIZE) as i32, 312, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_3 = Rect::new((i * TILE_SIZE) as i32, 344, TILE_SIZE, 2 * TILE_SIZE);
-    let pos_4 = Rect::new((i * TILE_SIZE) as i32, 376, TILE_SIZE, 2 * TILE_SIZE);
-
-    wincan.copy(&grass_sheet, src, pos_1)?;
-    wincan.copy(&grass_sheet, src, pos_2)?;
-    wincan.copy(&grass_sheet, src, pos_3)?;
-    wincan.copy(&grass_sheet, src, pos_4)?;
-
-    i += 1;
+  /// Where the player stands in front of a door, facing it
+  fn in_front_of(door: Rect) -> (i32, i32) {
+    (door.center().x() - TILE_SIZE as i32, door.bottom() + 1)
   }
 
-  Ok(())
+  /// Knocks on a door: gyms and the champion ask before letting the player in, or say why they are locked
+  ///
+  /// Doors into buildings and to spawn points are walked through, like going into another area.
+  fn open_door<'a>(&mut self, game: &mut Game<'a>, target: WarpTarget, door: Rect) -> Result<Transition<'a>, String> {
+    let player_box = self.player_box();
+    let gyms = game.gyms;
+    let (front_x, front_y) = OverworldScene::in_front_of(door);
+    let dialogue = match target {
+      // Each gym stays locked until the badges of the gyms before it are earned
+      WarpTarget::Gym(gym_index) if gym::gym_unlocked(&game.player_badges, gym_index) => {
+        // Come back out in front of the gym
+        self.stand_at(front_x, front_y);
+        DialogueScene::new(game, "enter_gym", "enter_gym", &[("theme", &gyms[gym_index].theme)])?.then(move |game, answer| {
+          if answer != Some(dialogue::YES) {
+            return Transition::None;
+          }
+          game.gym_no = gym_index;
+          Transition::Push(Box::new(gym::GymScene::new(game, gym_index)))
+        })
+      }
+      WarpTarget::Gym(gym_index) => DialogueScene::new(game, "gym_locked", "gym_locked", &[("badge", &gyms[gym_index - 1].badge)])?,
+      WarpTarget::Champion if game.player_badges.len() >= gym::BADGE_COUNT => {
+        self.stand_at(front_x, front_y);
+        DialogueScene::new(game, "enter_champion", "enter_champion", &[])?.then(|game, answer| {
+          if answer == Some(dialogue::YES) {
+            champion_battle(game)
+          } else {
+            Transition::None
+          }
+        })
+      }
+      WarpTarget::Champion => DialogueScene::new(game, "champion_locked", "champion_locked", &[])?,
+      WarpTarget::Interior(area) => {
+        self.stand_at(player_box.x(), player_box.y());
+        self.door = Some((self.area, door));
+        self.leaving = Some((ui::Fade::new(AREA_FADE), game.world.entrance(&area)));
+        return Ok(Transition::None);
+      }
+      WarpTarget::Exit => {
+        // Players who did not come in through a door, like those who wake up inside, leave through the first one
+        let (area, door) = self.door.take().unwrap_or_else(|| game.world.door(game.world.name(self.area)));
+        let (x, y) = OverworldScene::in_front_of(door);
+        self.stand_at(player_box.x(), player_box.y());
+        self.leaving = Some((ui::Fade::new(AREA_FADE), (area, x, y)));
+        return Ok(Transition::None);
+      }
+      WarpTarget::Spawn(name) => {
+        self.stand_at(player_box.x(), player_box.y());
+        self.door = None;
+        self.leaving = Some((ui::Fade::new(AREA_FADE), game.world.spawn(&name)));
+        return Ok(Transition::None);
+      }
+    };
+    Ok(Transition::Push(Box::new(dialogue)))
+  }
 }
 
-pub fn display_menu(wincan: &mut sdl2::render::WindowCanvas, player_x: i32, player_y: i32) -> Result<(), String>{
-  let texture_creator = wincan.texture_creator();
-  let fight_tab = texture_creator.load_texture("images/pressF.png")?;
-  let bail_tab = texture_creator.load_texture("images/bail.png")?;
-
-  // Add the fight tab
-  let src_f = Rect::new(0, 0, 128, 64);
-  let mut pos_f = Rect::new(player_x - 20, player_y - 140, 128, 64);
-
-  
-  // Add the bail tab
-  let src_b = Rect::new(0, 0, 128, 64);
-  let mut pos_b = Rect::new(player_x - 20, player_y - 140 + 64, 128, 64);
-  
-  if pos_f.y < 0 || pos_b.y < 0{
-    pos_f = Rect::new(player_x - 20, player_y + 50, 128, 64);
-    pos_b = Rect::new(player_x - 20, player_y + 50 + 64, 128, 64);
-  }
-  wincan.copy(&fight_tab, src_f, pos_f)?;
-  wincan.copy(&bail_tab, src_b, pos_b)?;
-  Ok(())
+/// Sets up a battle against the trainer of the area with the given id
+pub fn trainer_battle<'a>(game: &mut Game<'a>, id: String) -> Transition<'a> {
+  let profile = ai::trainer_profile(game.ai_profiles, game.trainer_profiles, &id);
+  let enemy_team = monster::select_random_team(
+    game.all_monsters,
+    2,
+    monster::experience(game.difficulty, game.player_badges.len()),
+  );
+  game.trainer = Some(id);
+  game.start_battle(enemy_team, &monster::BattleType::Trainer, None, profile, None)
 }
 
+/// Sets up the battle against the champion
+fn champion_battle<'a>(game: &mut Game<'a>) -> Transition<'a> {
+  let gyms = game.gyms;
+  let champion = &gyms[gym::BADGE_COUNT];
+  let profile = game
+    .ai_profiles
+    .get(&champion.leader_profile)
+    .cloned()
+    .unwrap_or_else(ai::AiProfile::balanced);
+  let victory_message = Some(format!("You are the new {}!", champion.badge));
+  let movesets = game.gym_movesets;
+  game.start_battle(
+    gym::gym_team(&champion.leader_team),
+    &monster::BattleType::Champion,
+    Some(&movesets[gym::BADGE_COUNT].1),
+    profile,
+    victory_message,
+  )
+}
+
+impl<'a> Scene<'a> for OverworldScene {
+  fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+    if self.encounter.is_some() || self.leaving.is_some() || self.challenge.is_some() || self.event.is_some() {
+      self.direction = (0, 0);
+      return Ok(Transition::None);
+    }
+    self.direction = player::movement_input(input);
+
+    if input.was_pressed(Action::Menu) {
+      return Ok(Transition::Push(Box::new(menu::TeamMenuScene::new())));
+    }
+    if input.was_pressed(Action::Badges) {
+      return Ok(Transition::Push(Box::new(gym::BadgeCaseScene)));
+    }
+    if input.was_pressed(Action::Journal) {
+      return Ok(Transition::Push(Box::new(quest::JournalScene::new())));
+    }
+    if input.was_pressed(Action::Options) {
+      return Ok(Transition::Push(Box::new(menu::OptionsScene::new())));
+    }
+
+    // Interact opens doors, talks to trainers and reads signs
+    if input.was_pressed(Action::Interact) {
+      let player_box = self.player_box();
+      let map = self.map(game);
+      if let Some(warp) = map.warp_at(&player_box) {
+        return self.open_door(game, warp.target.clone(), warp.rect);
+      }
+      if let Some(trainer) = self.touched_trainer() {
+        return self.talk_to(game, trainer);
+      }
+      if let Some(sign) = map.sign_at(&player_box) {
+        return Ok(Transition::Push(Box::new(DialogueScene::new(game, &sign.script, &sign.script, &[])?)));
+      }
+    }
+
+    Ok(Transition::None)
+  }
 
-  pub fn display_building_menu(wincan: &mut WindowCanvas) -> Result<(), String> {
-    let texture_creator = wincan.texture_creator();
-    let display_gym_box = texture_creator.load_texture("images/enterbuilding.png").unwrap();
-  
-    let display_box = Rect::new(500, 200, 200 ,200);
-    wincan.copy(&display_gym_box, None, display_box)?;
+  fn update(&mut self, game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String> {
+    // The wild monster jumps out once the screen has gone dark
+    if let Some((fade, _)) = self.encounter.as_mut() {
+      if !fade.advance(dt) {
+        return Ok(Transition::None);
+      }
+      let (_, wild_monster) = self.encounter.take().unwrap();
+      return Ok(game.start_battle(vec![wild_monster], &monster::BattleType::Wild, None, ai::AiProfile::balanced(), None));
+    }
+
+    // The next area is shown once the screen has gone dark
+    if let Some((fade, (area, x, y))) = self.leaving.as_mut() {
+      if fade.advance(dt) {
+        let (area, x, y) = (*area, *x, *y);
+        self.leaving = None;
+        self.enter_area(game, area, x, y);
+      }
+      return Ok(Transition::None);
+    }
+
+    // Everyone waits on the event being run, apart from who it moves
+    if let Some(event) = self.event.as_mut() {
+      self.player.animate((0, 0), dt);
+      for npc in self.npcs.iter_mut() {
+        npc.idle(dt);
+      }
+      match event.step(game, &mut self.npcs, dt)? {
+        Step::Wait => {}
+        Step::Show(transition) => return Ok(transition),
+        Step::Done => self.event = None,
+      }
+      return Ok(Transition::None);
+    }
+
+    // A trainer who spotted the player walks up to them, then speaks before the battle
+    let player_box = self.player_box();
+    if let Some(challenge) = self.challenge.as_mut() {
+      let trainer = challenge.trainer;
+      self.player.animate((0, 0), dt);
+      if challenge.advance(&mut self.npcs[trainer], &player_box, dt) {
+        self.challenge = None;
+        return self.talk_to(game, trainer);
+      }
+      return Ok(Transition::None);
+    }
+
+    // Quests finished since the last update hand out their rewards
+    let finished = quest::finish_quests(game)?;
+    if !matches!(finished, Transition::None) {
+      self.stand_at(player_box.x(), player_box.y());
+      return Ok(finished);
+    }
+
+    self.body.step(self.direction, dt);
+    self.sync_player();
+    self.player.animate(self.direction, dt);
+
+    // Walking off the edge of the area leads into the one next to it
+    let map = self.map(game);
+    if let Some(edge) = map.edge_crossed(&self.player_box()) {
+      let arrival = game.world.arrival(edge, &self.player_box());
+      self.body.stop();
+      self.leaving = Some((ui::Fade::new(AREA_FADE), arrival));
+      return Ok(Transition::None);
+    }
+
+    // Trainers move about, but never into the player or anything solid
+    let player_box = self.player_box();
+    for npc in self.npcs.iter_mut() {
+      npc.update(dt, |r| map.is_blocked(r) || r.has_intersection(player_box));
+    }
+
+    // Check for collision between player and buildings, solid tiles and the edges of the map
+    // Use the "go-back" approach to collision resolution
+    if map.is_blocked(&self.player_box()) {
+      self.body.go_back();
+      self.sync_player();
+    }
+
+    // Walking into a trigger starts its event
+    let player_box = self.player_box();
+    let trigger = map.trigger_at(&player_box);
+    if let (Some(index), true) = (trigger, trigger != self.trigger) {
+      self.trigger = trigger;
+      self.stand_at(player_box.x(), player_box.y());
+      self.event = Some(EventRunner::new(game.events, &map.triggers[index].event));
+      return Ok(Transition::None);
+    }
+    self.trigger = trigger;
+
+    // Trainers who have not been beaten come over to battle once they spot the player
+    let player_box = self.player_box();
+    if game.last_battle.elapsed().as_secs_f64() > ENCOUNTER_GRACE {
+      let spotted = self.npcs.iter().position(|npc| {
+        !game.defeated_trainers.contains(&npc.id) && npc.spots(&player_box, |r| map.is_blocked(r))
+      });
+      if let Some(trainer) = spotted {
+        self.stand_at(player_box.x(), player_box.y());
+        self.challenge = Some(Challenge::new(trainer));
+        return Ok(Transition::None);
+      }
+    }
+
+    // Stop next to trainers who have not been beaten so the player can choose to battle them
+    if self.touched_trainer().is_some_and(|trainer| !game.defeated_trainers.contains(&self.npcs[trainer].id)) {
+      self.body.stop();
+      return Ok(Transition::None);
+    }
+
+    // Wild monsters hide in the grass, water and rocks, and may jump out with each step there
+    self.walked += self.body.stride();
+    if self.walked < STEP_LENGTH {
+      return Ok(Transition::None);
+    }
+    self.walked -= STEP_LENGTH;
+
+    let player_box = self.player_box();
+    let area = game.world.name(self.area);
+    let table = map.encounter_zone(&player_box).and_then(|zone| game.encounters.table(area, zone));
+    if let Some(table) = table {
+      let mut rng = rand::thread_rng();
+      if game.last_battle.elapsed().as_secs_f64() > ENCOUNTER_GRACE && table.roll_step(&mut rng) {
+        self.stand_at(player_box.x(), player_box.y());
+        self.encounter = Some((ui::Fade::new(ENCOUNTER_FADE), table.choose(&mut rng)));
+      }
+    }
+
+    Ok(Transition::None)
+  }
+
+  fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, alpha: f64) -> Result<(), String> {
+    let map = self.map(game);
+    let (draw_x, draw_y) = self.body.drawn_at(alpha);
+    let drawn_player = Rect::new(draw_x, draw_y, self.player.width(), self.player.height());
+    let camera = Camera::following(drawn_player, map.width(), map.height());
+    map.draw(wincan, &game.assets, &camera)?;
+
+    for npc in self.npcs.iter() {
+      npc.draw(wincan, &game.assets, game.sprites, camera.to_screen(npc.drawn_at(alpha)))?;
+    }
+    self.player.draw(wincan, &game.assets, game.sprites, camera.to_screen(drawn_player))?;
+    if let Some(challenge) = &self.challenge {
+      let trainer = camera.to_screen(self.npcs[challenge.trainer].drawn_at(alpha));
+      challenge.draw(wincan, &game.assets, trainer)?;
+    }
+
+    // Say what Interact does where the player is standing
+    let player_box = self.player_box();
+    let hint = if let Some(warp) = map.warp_at(&player_box) {
+      Some(match warp.target {
+        WarpTarget::Exit => "Leave",
+        WarpTarget::Spawn(_) => "Go through",
+        _ => "Enter",
+      })
+    } else if self.touched_trainer().is_some() {
+      Some("Talk")
+    } else if map.sign_at(&player_box).is_some() {
+      Some("Read")
+    } else {
+      None
+    };
+    if let (true, Some(hint)) = (self.challenge.is_none(), hint) {
+      ui::draw_hint(wincan, &game.assets, &format!("{}: {}", game.bindings.describe(Action::Interact), hint))?;
+    }
+
+    if let Some((fade, _)) = &self.encounter {
+      fade.draw(wincan)?;
+    }
+    if let Some((fade, _)) = &self.leaving {
+      fade.draw(wincan)?;
+    }
 
     Ok(())
   }
 
-  pub fn draw_hospital(wincan: &mut WindowCanvas)->Result<(), String>{
-    let hospital_screen = Rect::new((0) as i32, (0) as i32, (1280) as u32, (720) as u32);
-    let _texture_creator = wincan.texture_creator();
-    wincan.set_draw_color(Color::RGBA(0, 128, 128, 255));
-    wincan.fill_rect(hospital_screen).unwrap();
+  fn kind(&self) -> SceneKind {
+    SceneKind::Overworld
+  }
+}
 
-     Ok(())
+/// The nurse's machine at work: the screen flashes while the team is healed
+pub struct HealScene {
+  elapsed: f64,
+}
+
+impl HealScene {
+  pub fn new() -> HealScene {
+    HealScene { elapsed: 0.0 }
   }
+}
 
-  pub fn draw_home(wincan: &mut WindowCanvas)->Result<(), String>{
-    let home_screen = Rect::new((0) as i32, (0) as i32, (1280) as u32, (720) as u32);
-    let _texture_creator = wincan.texture_creator();
-    wincan.set_draw_color(Color::RGBA(0, 128, 128, 255));
-    wincan.fill_rect(home_screen).unwrap();
+impl Default for HealScene {
+  fn default() -> Self {
+    HealScene::new()
+  }
+}
 
-    Ok(())
+impl<'a> Scene<'a> for HealScene {
+  fn handle_input(&mut self, _game: &mut Game<'a>, _input: &Input) -> Result<Transition<'a>, String> {
+    Ok(Transition::None)
   }
 
-pub fn mark_rectangles() -> Vec<Rect>{
-  let mut spn_rectangles = Vec::new();
-  // Top left corner of the grass patches
-  let left_corner_grass = Rect::new((6*TILE_SIZE) as i32, 96, 2*TILE_SIZE*7, 2*TILE_SIZE*4);
-  spn_rectangles.push(left_corner_grass);
-  // A pond to the right bottom corner of map
-  let right_bottom_pond = Rect::new((48*TILE_SIZE) as i32, 480, 2*TILE_SIZE*10, 2*TILE_SIZE*4);
-  spn_rectangles.push(right_bottom_pond);
-  // Rock patches to right upper corner of map
-  let right_upper_rock = Rect::new((60*TILE_SIZE) as i32, 66, 2*TILE_SIZE*9, 2*TILE_SIZE*5);
-  spn_rectangles.push(right_upper_rock);
-  // Large grass patches to north of map
-  let north_grass = Rect::new((32*TILE_SIZE) as i32, 96, 2*TILE_SIZE*10, 2*TILE_SIZE*4);
-  spn_rectangles.push(north_grass);
-  // Grass patches to the south of map
-  let south_grass = Rect::new((32*TILE_SIZE) as i32, 480, 2*TILE_SIZE*7, 2*TILE_SIZE*4);
-  spn_rectangles.push(south_grass);
-  // Grass patches to the center right of map
-  let center_right_grass = Rect::new((62*TILE_SIZE) as i32, 300, 2*TILE_SIZE*8, 2*TILE_SIZE*4);
-  spn_rectangles.push(center_right_grass);
-  // A pond to the middle center left of map
-  let center_left_pond = Rect::new((4*TILE_SIZE) as i32, 280, 2*TILE_SIZE*8, 2*TILE_SIZE*4);
-  spn_rectangles.push(center_left_pond);
-  // Small grass patches in the center of the map
-  let center_grass = Rect::new((24*TILE_SIZE) as i32, 280, 2*TILE_SIZE*6, 2*TILE_SIZE*4);
-  spn_rectangles.push(center_grass);
-
-  return spn_rectangles;
+  fn update(&mut self, _game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String> {
+    self.elapsed += dt;
+    if self.elapsed >= HEAL_TIME {
+      Ok(Transition::Pop)
+    } else {
+      Ok(Transition::None)
+    }
+  }
+
+  fn render(&self, _game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+    let flash = (self.elapsed / HEAL_TIME * HEAL_FLASHES * std::f64::consts::PI).sin().abs();
+    wincan.set_draw_color(Color::RGBA(255, 255, 255, (flash * 160.0) as u8));
+    wincan.fill_rect(Rect::new(0, 0, camera::CAM_W, camera::CAM_H))
+  }
+
+  fn is_overlay(&self) -> bool {
+    true
+  }
+
+  fn kind(&self) -> SceneKind {
+    SceneKind::Healing
+  }
 }
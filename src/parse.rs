@@ -0,0 +1,8 @@
+//! Helpers shared by the loaders of the data files
+
+/// Reads a number from a field of a data file, naming the line in the error if it is not one
+pub fn parse_number<T: std::str::FromStr>(s: &str, line_number: usize) -> Result<T, String> {
+    s.trim()
+        .parse::<T>()
+        .map_err(|_| format!("line {}: {} is not a number", line_number, s))
+}
@@ -1,17 +1,28 @@
 use sdl2::rect::Rect;
-use sdl2::render::Texture;
+use sdl2::render::WindowCanvas;
 
-pub struct Player<'a> {
-  //delta_x_npc: i32,
+use crate::assets::AssetManager;
+use crate::sprite::{Animator, Facing, Sprites};
+use crate::input::{Action, Input};
+
+// Top walking speed, in pixels per second
+pub const MAX_SPEED: f64 = 300.0;
+// How quickly walking speeds up and slows down, in pixels per second per second
+pub const ACCEL_RATE: f64 = 3600.0;
+
+/// The player as they appear in the world: where they stand, and how they are animated
+pub struct Player {
   pos: Rect,
-  texture: Texture<'a>,
-  //flip: bool
+  animator: Animator,
 }
 
-impl<'a> Player<'a> {
+impl Player {
   //Create a new instance of player struct
-  pub fn create(pos: Rect, texture: Texture<'a>) -> Player {
-    Player { pos, texture }
+  pub fn create(pos: Rect) -> Player {
+    Player {
+      pos,
+      animator: Animator::new("player", Facing::Down),
+    }
   }
 
   pub fn x(&self) -> i32 {
@@ -26,25 +37,131 @@ impl<'a> Player<'a> {
   pub fn height(&self) -> u32 {
     self.pos.height()
   }
-  pub fn _set_x(&mut self, x_cor: i32) {
+  pub fn set_x(&mut self, x_cor: i32) {
     self.pos.set_x(x_cor);
   }
-  pub fn _set_y(&mut self, y_cor: i32) {
+  pub fn set_y(&mut self, y_cor: i32) {
     self.pos.set_y(y_cor);
   }
-  /*pub fn update_pos_x(&mut self, vel_x: i32, x_left_bound: i32, x_right_bound: i32) {
-    self
-      .pos
-      .set_x((self.pos.x() + vel_x).clamp(x_left_bound, x_right_bound));
+
+  /// Turns and walks towards `direction`, or idles without one
+  pub fn animate(&mut self, direction: (i32, i32), dt: f64) {
+    self.animator.walk(direction, dt);
+  }
+
+  pub fn draw(&self, wincan: &mut WindowCanvas, assets: &AssetManager, sprites: &Sprites, dest: Rect) -> Result<(), String> {
+    self.animator.draw(wincan, assets, sprites, dest)
   }
+}
 
-  pub fn update_pos_y(&mut self, vel_y: i32, y_left_bound: i32, y_right_bound: i32){
-    self
-      .pos
-      .set_y((self.pos.y() + vel_y).clamp(y_left_bound, y_right_bound));
-  }*/
+/// Something that walks around: where it is, how fast it is going, and where it was before
+/// the last update, so that frames drawn between updates can show it part of the way there
+///
+/// Velocities are in pixels per second.
+pub struct Body {
+  x: f64,
+  y: f64,
+  prev_x: f64,
+  prev_y: f64,
+  x_vel: f64,
+  y_vel: f64,
+}
 
-  pub fn texture(&self) -> &Texture {
-    &self.texture
+impl Body {
+  pub fn new(x: i32, y: i32) -> Body {
+    Body {
+      x: x as f64,
+      y: y as f64,
+      prev_x: x as f64,
+      prev_y: y as f64,
+      x_vel: 0.0,
+      y_vel: 0.0,
+    }
+  }
+
+  pub fn x(&self) -> i32 {
+    self.x.round() as i32
   }
+  pub fn y(&self) -> i32 {
+    self.y.round() as i32
+  }
+
+  /// Speeds up towards `direction`, or slows down without one, then moves for `dt` seconds
+  pub fn step(&mut self, direction: (i32, i32), dt: f64) {
+    self.prev_x = self.x;
+    self.prev_y = self.y;
+    self.x_vel = accelerate(self.x_vel, direction.0, dt);
+    self.y_vel = accelerate(self.y_vel, direction.1, dt);
+    self.x += self.x_vel * dt;
+    self.y += self.y_vel * dt;
+  }
+
+  /// Undoes the last step; used for the "go-back" approach to collision resolution
+  pub fn go_back(&mut self) {
+    self.x = self.prev_x;
+    self.y = self.prev_y;
+  }
+
+  /// How far the last step went, in pixels
+  pub fn stride(&self) -> f64 {
+    (self.x - self.prev_x).hypot(self.y - self.prev_y)
+  }
+
+  pub fn stop(&mut self) {
+    self.x_vel = 0.0;
+    self.y_vel = 0.0;
+  }
+
+  /// Moves straight to a position and stands still there
+  pub fn place(&mut self, x: i32, y: i32) {
+    *self = Body::new(x, y);
+  }
+
+  /// Where to draw the body, `alpha` of the way from its previous position to its current one
+  pub fn drawn_at(&self, alpha: f64) -> (i32, i32) {
+    (
+      (self.prev_x + (self.x - self.prev_x) * alpha).round() as i32,
+      (self.prev_y + (self.y - self.prev_y) * alpha).round() as i32,
+    )
+  }
+}
+
+/// Returns the direction asked for by the movement keys being held
+pub fn movement_input(input: &Input) -> (i32, i32) {
+  let mut x_dir = 0;
+  let mut y_dir = 0;
+  if input.is_held(Action::Up) {
+    y_dir -= 1;
+  }
+  if input.is_held(Action::Left) {
+    x_dir -= 1;
+  }
+  if input.is_held(Action::Down) {
+    y_dir += 1;
+  }
+  if input.is_held(Action::Right) {
+    x_dir += 1;
+  }
+  (x_dir, y_dir)
+}
+
+/// Accelerates a velocity towards a direction (-1, 0 or 1) for `dt` seconds; with no direction
+/// it slows down to a stop instead, and it never exceeds the speed limit
+pub fn accelerate(vel: f64, direction: i32, dt: f64) -> f64 {
+  if direction == 0 {
+    (vel.abs() - ACCEL_RATE * dt).max(0.0).copysign(vel)
+  } else {
+    (vel + direction as f64 * ACCEL_RATE * dt).clamp(-MAX_SPEED, MAX_SPEED)
+  }
+}
+
+pub fn check_collision(a: &Rect, b: &Rect) -> bool {
+  !(a.bottom() < b.top() || a.top() > b.bottom() || a.right() < b.left() || a.left() > b.right())
+}
+
+pub fn check_within(small: &Rect, large: &Rect) -> bool {
+  small.left() > large.left()
+    && small.right() < large.right()
+    && small.top() > large.top()
+    && small.bottom() < large.bottom()
 }
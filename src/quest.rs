@@ -0,0 +1,376 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::battle;
+use crate::dialogue::DialogueScene;
+use crate::gym;
+use crate::input::{Action, Input};
+use crate::item::Items;
+use crate::monster::Monster;
+use crate::parse::parse_number;
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::shop;
+use crate::ui;
+
+pub const QUEST_FILE: &str = "./data/quests.txt";
+
+// Counts wild monsters of every type at once
+const ANY: &str = "any";
+
+// How many quests the journal lists at once
+const JOURNAL_ROWS: usize = 8;
+
+/// Something a quest asks the player to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum Objective {
+    /// Beat trainers, only counting those of one gym if it is given
+    Trainers(Option<usize>, usize),
+    /// Beat wild monsters, only counting those of one type if it is given
+    Wild(Option<String>, usize),
+    /// Train a monster on the team up to a level
+    Level(usize),
+    Badges(usize),
+    /// Get an event to set a flag, described in the journal by the text given
+    Flag(String, String),
+}
+
+impl Objective {
+    /// How far along the player is, and how far they have to get
+    pub fn progress(&self, game: &Game) -> (usize, usize) {
+        let (done, needed) = match self {
+            Objective::Trainers(gym_no, count) => {
+                let prefix = gym_no.map(|g| format!("gym_{}_", g));
+                let beaten = game
+                    .defeated_trainers
+                    .iter()
+                    .filter(|t| prefix.as_ref().is_none_or(|p| t.starts_with(p.as_str())))
+                    .count();
+                let forgotten: u32 = game
+                    .quest_log
+                    .gym_trainers
+                    .iter()
+                    .filter(|(g, _)| gym_no.is_none_or(|gym_no| **g == gym_no))
+                    .map(|(_, count)| count)
+                    .sum();
+                (beaten + forgotten as usize, *count)
+            }
+            Objective::Wild(monster_type, count) => {
+                let beaten = game.quest_log.wild.get(monster_type.as_deref().unwrap_or(ANY)).copied().unwrap_or(0);
+                (beaten as usize, *count)
+            }
+            Objective::Level(level) => {
+                // Levels are a tenth of a monster's experience
+                let best = game.battle_state.player_team.iter().map(|m| m.2 / 10).max().unwrap_or(0);
+                (best, *level)
+            }
+            Objective::Badges(count) => (game.player_badges.len(), *count),
+            Objective::Flag(flag, _) => (game.flags.contains(flag) as usize, 1),
+        };
+        (done.min(needed), needed)
+    }
+
+    /// What the journal says the objective is
+    pub fn describe(&self, game: &Game) -> String {
+        match self {
+            Objective::Trainers(Some(gym_no), count) => format!("Beat {} trainers in the {} Gym", count, game.gyms[*gym_no].theme),
+            Objective::Trainers(None, count) => format!("Beat {} trainers", count),
+            Objective::Wild(Some(monster_type), count) => format!("Beat {} wild {} type monsters", count, monster_type),
+            Objective::Wild(None, count) => format!("Beat {} wild monsters", count),
+            Objective::Level(level) => format!("Train a monster to level {}", level),
+            Objective::Badges(count) => format!("Earn {} badges", count),
+            Objective::Flag(_, text) => text.clone(),
+        }
+    }
+}
+
+/// What the player gets for finishing a quest
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reward {
+    Item(String, u32),
+    Money(u32),
+    /// Experience for every monster on the team
+    Experience(usize),
+}
+
+impl Reward {
+    fn describe(&self) -> String {
+        match self {
+            Reward::Item(name, count) => format!("{} x{}", name, count),
+            Reward::Money(amount) => shop::describe_money(*amount),
+            Reward::Experience(amount) => format!("{} experience", amount),
+        }
+    }
+}
+
+pub struct Quest {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    pub rewards: Vec<Reward>,
+}
+
+impl Quest {
+    fn is_done(&self, game: &Game) -> bool {
+        self.objectives.iter().all(|o| {
+            let (done, needed) = o.progress(game);
+            done >= needed
+        })
+    }
+}
+
+/// Every quest, loaded from `data/quests.txt`, in the order the journal lists them
+///
+/// The file has one record per line after a header line:
+///
+/// * `quest,id,title,description` - The description can have commas
+/// * `objective,quest,trainers,gym,count` - Beat trainers of a gym by number, or `any` trainers
+/// * `objective,quest,wild,type,count` - Beat wild monsters of a type, or of `any` type
+/// * `objective,quest,level,level` - Train a monster on the team up to a level
+/// * `objective,quest,badges,count`
+/// * `objective,quest,flag,flag,text` - Get an event to set a flag; the text says what to do
+/// * `reward,quest,item,name,count`, `reward,quest,money,amount` or `reward,quest,exp,amount` -
+///   Experience goes to every monster on the team
+///
+/// Every quest is open from the start, and is finished once all of its objectives are met.
+pub struct Quests {
+    quests: Vec<Quest>,
+}
+
+impl Quests {
+    /// Loads every quest, making sure the gyms, types and items they name exist
+    pub fn load(path: &str, monsters: &HashMap<String, Monster>, items: &Items) -> Result<Quests, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        let types: HashSet<&str> = monsters.values().map(|m| m.monster_type.as_str()).collect();
+        Quests::parse(&contents, &types, items).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn parse(contents: &str, types: &HashSet<&str>, items: &Items) -> Result<Quests, String> {
+        let mut quests: Vec<Quest> = Vec::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            if v[0] == "quest" && v.len() >= 4 {
+                if quests.iter().any(|q| q.id == v[1]) {
+                    return Err(format!("line {}: there is already a quest called {}", line_number, v[1]));
+                }
+                quests.push(Quest {
+                    id: v[1].to_string(),
+                    title: v[2].to_string(),
+                    description: line.trim().splitn(4, ',').nth(3).unwrap_or("").to_string(),
+                    objectives: Vec::new(),
+                    rewards: Vec::new(),
+                });
+                continue;
+            }
+
+            let quest = match v[..] {
+                [""] => continue,
+                ["objective", quest, ..] | ["reward", quest, ..] => quests
+                    .iter_mut()
+                    .find(|q| q.id == quest)
+                    .ok_or(format!("line {}: unknown quest {}", line_number, quest))?,
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            };
+            match v[..] {
+                ["objective", _, "trainers", gym_no, count] => {
+                    let gym_no = match gym_no {
+                        ANY => None,
+                        _ => Some(parse_number(gym_no, line_number)?).filter(|g| *g < gym::BADGE_COUNT),
+                    };
+                    if gym_no.is_none() && v[3] != ANY {
+                        return Err(format!("line {}: there is no gym {}", line_number, v[3]));
+                    }
+                    quest.objectives.push(Objective::Trainers(gym_no, parse_number(count, line_number)?));
+                }
+                ["objective", _, "wild", monster_type, count] => {
+                    if monster_type != ANY && !types.contains(monster_type) {
+                        return Err(format!("line {}: no monster is a {} type", line_number, monster_type));
+                    }
+                    let monster_type = Some(monster_type.to_string()).filter(|t| t != ANY);
+                    quest.objectives.push(Objective::Wild(monster_type, parse_number(count, line_number)?));
+                }
+                ["objective", _, "level", level] => quest.objectives.push(Objective::Level(parse_number(level, line_number)?)),
+                ["objective", _, "badges", count] => quest.objectives.push(Objective::Badges(parse_number(count, line_number)?)),
+                ["objective", _, "flag", flag, text] => quest.objectives.push(Objective::Flag(flag.to_string(), text.to_string())),
+                ["reward", _, "item", name, count] => {
+                    if items.get(name).is_none() {
+                        return Err(format!("line {}: there is no item {}", line_number, name));
+                    }
+                    quest.rewards.push(Reward::Item(name.to_string(), parse_number(count, line_number)?));
+                }
+                ["reward", _, "money", amount] => quest.rewards.push(Reward::Money(parse_number(amount, line_number)?)),
+                ["reward", _, "exp", amount] => quest.rewards.push(Reward::Experience(parse_number(amount, line_number)?)),
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        if let Some(quest) = quests.iter().find(|q| q.objectives.is_empty()) {
+            return Err(format!("{} has no objectives", quest.id));
+        }
+
+        Ok(Quests { quests })
+    }
+}
+
+/// The player's progress on quests that is not kept anywhere else
+#[derive(Debug, Clone, Default)]
+pub struct QuestLog {
+    /// The quests that are finished and have handed out their rewards
+    pub completed: HashSet<String>,
+    /// How many wild monsters of each type the player has beaten, and of `any` type
+    pub wild: HashMap<String, u32>,
+    /// How many trainers of each gym the player beat in mazes that have since been remade
+    pub gym_trainers: HashMap<usize, u32>,
+}
+
+impl QuestLog {
+    /// Counts a wild monster the player has beaten
+    pub fn beat_wild(&mut self, monster_type: &str) {
+        *self.wild.entry(monster_type.to_string()).or_insert(0) += 1;
+        *self.wild.entry(ANY.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Finishes every quest whose objectives are all met, handing out its rewards
+///
+/// Returns a message about the first quest finished, to show over the scene; the rest are
+/// shown the next time this is called.
+pub fn finish_quests<'a>(game: &mut Game<'a>) -> Result<Transition<'a>, String> {
+    let quests = game.quests;
+    let quest = match quests.quests.iter().find(|q| !game.quest_log.completed.contains(&q.id) && q.is_done(game)) {
+        Some(quest) => quest,
+        None => return Ok(Transition::None),
+    };
+
+    for reward in quest.rewards.iter() {
+        match reward {
+            Reward::Item(name, count) => *game.items.entry(name.clone()).or_insert(0) += count,
+            Reward::Money(amount) => game.money += amount,
+            Reward::Experience(amount) => {
+                for monster in game.battle_state.player_team.iter_mut() {
+                    monster.2 += amount;
+                }
+                game.battle_state.player_team = battle::verify_team(&game.battle_state.player_team);
+            }
+        }
+    }
+    game.quest_log.completed.insert(quest.id.clone());
+    game.save()?;
+
+    let rewards: Vec<String> = quest.rewards.iter().map(|r| r.describe()).collect();
+    let mut text = format!("Quest complete: {}!", quest.title);
+    if !rewards.is_empty() {
+        text += &format!(" You got {}.", rewards.join(", "));
+    }
+    Ok(Transition::Push(Box::new(DialogueScene::message(game, "Journal", &text)?)))
+}
+
+/// The list of quests, with the objectives of the one picked and how far along they are
+pub struct JournalScene {
+    choice: usize,
+}
+
+impl JournalScene {
+    pub fn new() -> JournalScene {
+        JournalScene { choice: 0 }
+    }
+}
+
+impl Default for JournalScene {
+    fn default() -> Self {
+        JournalScene::new()
+    }
+}
+
+impl<'a> Scene<'a> for JournalScene {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        let count = game.quests.quests.len().max(1);
+        if input.was_repeated(Action::Up) {
+            self.choice = (self.choice + count - 1) % count;
+        }
+        if input.was_repeated(Action::Down) {
+            self.choice = (self.choice + 1) % count;
+        }
+        if input.was_pressed(Action::Cancel) || input.was_pressed(Action::Journal) {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, _game: &mut Game<'a>, _dt: f64) -> Result<Transition<'a>, String> {
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        let text_color = Color::RGB(0xbd, 0xcd, 0xde);
+        wincan.set_draw_color(Color::RGB(0x20, 0x41, 0x6a));
+        wincan.fill_rect(Rect::new(140, 40, 1000, 640))?;
+        ui::draw_text(wincan, &game.assets, "Journal", text_color, Rect::new(440, 50, 400, 50))?;
+
+        // The list scrolls to keep the quest picked in view
+        let quests = &game.quests.quests;
+        let first = (self.choice + 1).saturating_sub(JOURNAL_ROWS);
+        for (row, quest) in quests.iter().enumerate().skip(first).take(JOURNAL_ROWS) {
+            let y = 120 + 60 * (row - first) as i32;
+            if row == self.choice {
+                wincan.set_draw_color(Color::RGB(0xf6, 0x52, 0x41));
+                wincan.fill_rect(Rect::new(160, y - 3, 360, 54))?;
+            }
+            wincan.set_draw_color(Color::RGB(0x39, 0x7B, 0xB4));
+            wincan.fill_rect(Rect::new(163, y, 354, 48))?;
+            let title = if game.quest_log.completed.contains(&quest.id) {
+                format!("{} (done)", quest.title)
+            } else {
+                quest.title.clone()
+            };
+            ui::draw_text(wincan, &game.assets, &title, Color::BLACK, Rect::new(175, y + 8, 330, 32))?;
+        }
+
+        let quest = match quests.get(self.choice) {
+            Some(quest) => quest,
+            None => return ui::draw_text(wincan, &game.assets, "No quests yet", text_color, Rect::new(175, 120, 330, 40)),
+        };
+        let mut y = 120;
+        for line in ui::wrap_text(&game.assets, &quest.description, 560, 30)? {
+            draw_line(wincan, game, &line, text_color, y)?;
+            y += 36;
+        }
+        y += 20;
+        for objective in quest.objectives.iter() {
+            let (done, needed) = objective.progress(game);
+            let color = if done >= needed { Color::RGB(0x7B, 0xD5, 0x6A) } else { Color::WHITE };
+            draw_line(wincan, game, &format!("{} ({}/{})", objective.describe(game), done, needed), color, y)?;
+            y += 40;
+        }
+        if !quest.rewards.is_empty() {
+            let rewards: Vec<String> = quest.rewards.iter().map(|r| r.describe()).collect();
+            draw_line(wincan, game, &format!("Reward: {}", rewards.join(", ")), text_color, y + 20)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Journal
+    }
+}
+
+/// Draws a line of the details of a quest, from the left edge of the details
+fn draw_line(wincan: &mut WindowCanvas, game: &Game, text: &str, color: Color, y: i32) -> Result<(), String> {
+    let texture = game.assets.text(text, color)?;
+    let query = texture.query();
+    let height = 30;
+    let width = ((query.width * height) / query.height).min(560);
+    wincan.copy(&texture, None, Rect::new(550, y, width, height))
+}
@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::parse::parse_number;
+
+pub const SAVE_FILE: &str = "./save.txt";
+
+/// The player's progress that is kept from one run of the game to the next
+///
+/// The save file has one record per line after a header line:
+///
+/// * `badge,gym number` - A badge the player has earned
+/// * `trainer,id` - A trainer the player has beaten
+/// * `flag,name` - A flag set by an event script
+/// * `item,name,count` - Items the player is carrying
+/// * `money,amount`
+/// * `quest,id` - A quest the player has finished
+/// * `wild,type,count` - How many wild monsters of a type the player has beaten, for quests
+/// * `gym_trainers,gym number,count` - How many trainers the player beat in earlier mazes of a gym, for quests
+/// * `respawn,spawn` - The spawn point where the player wakes up after blacking out
+#[derive(Debug, Clone, Default)]
+pub struct SaveState {
+    pub badges: HashSet<u32>,
+    pub defeated_trainers: HashSet<String>,
+    pub flags: HashSet<String>,
+    pub items: HashMap<String, u32>,
+    pub money: u32,
+    pub completed_quests: HashSet<String>,
+    pub wild_beaten: HashMap<String, u32>,
+    pub gym_trainers_beaten: HashMap<usize, u32>,
+    /// Only set once the player has healed somewhere
+    pub respawn: Option<String>,
+}
+
+impl SaveState {
+    /// Loads the saved progress; without a save file, the game starts from nothing
+    pub fn load(path: &str) -> Result<SaveState, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => SaveState::parse(&contents).map_err(|e| format!("{}: {}", path, e)),
+            Err(_) => Ok(SaveState::default()),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<SaveState, String> {
+        let mut state = SaveState::default();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            match v[..] {
+                [""] => {}
+                ["badge", gym_no] => {
+                    state.badges.insert(parse_number(gym_no, line_number)?);
+                }
+                ["trainer", id] => {
+                    state.defeated_trainers.insert(id.to_string());
+                }
+                ["flag", name] => {
+                    state.flags.insert(name.to_string());
+                }
+                ["item", name, count] => {
+                    state.items.insert(name.to_string(), parse_number(count, line_number)?);
+                }
+                ["money", amount] => state.money = parse_number(amount, line_number)?,
+                ["quest", id] => {
+                    state.completed_quests.insert(id.to_string());
+                }
+                ["wild", monster_type, count] => {
+                    state.wild_beaten.insert(monster_type.to_string(), parse_number(count, line_number)?);
+                }
+                ["gym_trainers", gym_no, count] => {
+                    state.gym_trainers_beaten.insert(parse_number(gym_no, line_number)?, parse_number(count, line_number)?);
+                }
+                ["respawn", spawn] => state.respawn = Some(spawn.to_string()),
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Writes the progress to the save file, sorted so that saves are easy to compare
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut records: Vec<String> = Vec::new();
+        records.extend(self.badges.iter().map(|b| format!("badge,{}", b)));
+        records.extend(self.defeated_trainers.iter().map(|t| format!("trainer,{}", t)));
+        records.extend(self.flags.iter().map(|f| format!("flag,{}", f)));
+        records.extend(self.items.iter().map(|(name, count)| format!("item,{},{}", name, count)));
+        records.push(format!("money,{}", self.money));
+        records.extend(self.completed_quests.iter().map(|q| format!("quest,{}", q)));
+        records.extend(self.wild_beaten.iter().map(|(t, count)| format!("wild,{},{}", t, count)));
+        records.extend(self.gym_trainers_beaten.iter().map(|(g, count)| format!("gym_trainers,{},{}", g, count)));
+        records.extend(self.respawn.iter().map(|s| format!("respawn,{}", s)));
+        records.sort();
+
+        let mut contents = String::from("record,data\n");
+        for record in records {
+            contents += &record;
+            contents.push('\n');
+        }
+        fs::write(path, contents).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+}
@@ -0,0 +1,251 @@
+use crate::ai;
+use crate::assets::AssetManager;
+use crate::battle;
+use crate::dialogue::Dialogues;
+use crate::encounter::Encounters;
+use crate::event::Events;
+use crate::gym;
+use crate::gym_layout::GymLayouts;
+use crate::input;
+use crate::item::Items;
+use crate::maze;
+use crate::monster;
+use crate::quest::{QuestLog, Quests};
+use crate::save::SaveState;
+use crate::sprite::Sprites;
+use crate::world::World;
+
+use sdl2::render::WindowCanvas;
+
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// The designed movesets of every gym: one per trainer team, and one for the leader
+pub type GymMovesets<'a> = Vec<(Vec<HashMap<String, Vec<&'a monster::Move>>>, HashMap<String, Vec<&'a monster::Move>>)>;
+
+/// Identifies what kind of screen a scene is; used by autoplay and crash reports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneKind {
+    Intro,
+    Overworld,
+    Gym,
+    /// A battle, with the move currently highlighted
+    Battle(usize),
+    /// The team menu, with the slot currently highlighted
+    TeamMenu(usize),
+    BadgeCase,
+    Credits,
+    Options,
+    Journal,
+    /// A dialogue box shown over another scene
+    Dialogue,
+    /// The team being healed, shown over another scene
+    Healing,
+    Shop,
+}
+
+/// What should happen to the scene stack after a scene handled a frame
+pub enum Transition<'a> {
+    None,
+    /// Put a new scene on top of the current one
+    Push(Box<dyn Scene<'a> + 'a>),
+    /// Remove the current scene, going back to the one below
+    Pop,
+    /// Swap the current scene for a new one
+    Replace(Box<dyn Scene<'a> + 'a>),
+    /// Throw away every scene and start over with the given ones (bottom first)
+    Reset(Vec<Box<dyn Scene<'a> + 'a>>),
+    Quit,
+}
+
+/// A single screen of the game
+pub trait Scene<'a> {
+    /// Reacts to the input of this frame
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &input::Input) -> Result<Transition<'a>, String>;
+
+    /// Advances the scene by one fixed step of `dt` seconds
+    fn update(&mut self, game: &mut Game<'a>, dt: f64) -> Result<Transition<'a>, String>;
+
+    /// Draws the scene; the canvas is presented once every visible scene is drawn
+    ///
+    /// Frames usually fall between two updates; `alpha` is how far along (0 to 1) the frame is
+    /// from the previous update to the next, so that moving things can be drawn in between.
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, alpha: f64) -> Result<(), String>;
+
+    /// Overlays are drawn on top of the scene below them instead of replacing it
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    fn kind(&self) -> SceneKind;
+}
+
+/// Everything shared between scenes: loaded data and the player's progress
+pub struct Game<'a> {
+    pub assets: AssetManager<'a>,
+    pub monsters: &'a HashMap<String, monster::Monster<'a>>,
+    pub all_monsters: &'a Vec<String>,
+    pub gyms: &'a Vec<gym::GymInfo>,
+    /// How each gym's maze is built and drawn
+    pub layouts: &'a GymLayouts,
+    pub world: &'a World,
+    pub encounters: &'a Encounters,
+    pub sprites: &'a Sprites,
+    pub dialogues: &'a Dialogues,
+    pub events: &'a Events,
+    pub quests: &'a Quests,
+    /// What every item is and what it costs
+    pub catalog: &'a Items,
+    pub gym_movesets: &'a GymMovesets<'a>,
+    pub ai_profiles: &'a HashMap<String, ai::AiProfile>,
+    pub trainer_profiles: &'a HashMap<String, String>,
+    pub battle_draw: battle::Battle<'a>,
+    pub battle_state: monster::BattleState<'a>,
+    pub enemy_profile: ai::AiProfile,
+    pub player_badges: HashSet<u32>,
+    /// The trainer being battled, if any; they are remembered as beaten once the player wins
+    pub trainer: Option<String>,
+    /// The trainers the player has beaten, who do not battle again
+    pub defeated_trainers: HashSet<String>,
+    /// Flags set by event scripts, which remember what has happened so far
+    pub flags: HashSet<String>,
+    /// The items the player is carrying, by name
+    pub items: HashMap<String, u32>,
+    pub money: u32,
+    pub quest_log: QuestLog,
+    /// The spawn point where the player wakes up after blacking out
+    pub respawn: String,
+    /// Where the player's progress is saved; the bot's games are not saved
+    pub save_file: Option<&'static str>,
+    pub difficulty: usize,
+    /// The gym the player entered last
+    pub gym_no: usize,
+    /// The maze of each gym, made once the difficulty is picked
+    pub gym_mazes: Vec<maze::Maze>,
+    /// The cells of each gym's maze the player has been in, until the maze is made again
+    pub gym_explored: Vec<Vec<Vec<bool>>>,
+    pub last_battle: Instant,
+    pub bindings: input::Bindings,
+}
+
+impl<'a> Game<'a> {
+    /// Sets up the state of a new battle and returns the transition into it
+    ///
+    /// * `enemy_team` - The opposing team
+    /// * `battle_type` - What kind of opponent is being battled
+    /// * `enemy_movesets` - The designed movesets of the opposing team, if any
+    /// * `enemy_profile` - The AI profile of the opponent
+    /// * `victory_message` - An extra message shown when the battle is won
+    pub fn start_battle(
+        &mut self,
+        enemy_team: Vec<(String, f32, usize)>,
+        battle_type: &'a monster::BattleType,
+        enemy_movesets: Option<&'a HashMap<String, Vec<&'a monster::Move>>>,
+        enemy_profile: ai::AiProfile,
+        victory_message: Option<String>,
+    ) -> Transition<'a> {
+        self.enemy_profile = enemy_profile;
+        self.battle_draw.victory_message = victory_message;
+        self.battle_draw.opp_level = enemy_team[0].2 / 10;
+        let enemy_team = battle::verify_team(&enemy_team);
+
+        self.battle_draw.enemy_name = enemy_team[0].0.clone();
+        self.battle_draw.enemy_health = 100.0;
+        self.battle_state.player_team = battle::verify_team(&self.battle_state.player_team);
+        self.battle_draw.player_name = monster::next_available_mon(&self.battle_state.player_team);
+        self.battle_draw.player_health = self.battle_state.player_team[0].1;
+        self.battle_draw.player_level = self.battle_state.player_team[0].2 / 10;
+
+        self.battle_state = monster::BattleState {
+            player_turn: true,
+            player_team: self.battle_state.player_team.clone(),
+            enemy_team,
+            self_attack_stages: 0,
+            self_defense_stages: 0,
+            opp_attack_stages: 0,
+            opp_defense_stages: 0,
+            player_badges: self.player_badges.len(),
+            battle_type,
+            enemy_turns: 0,
+            enemy_movesets,
+        };
+
+        Transition::Push(Box::new(battle::BattleScene::new()))
+    }
+
+    /// Writes the player's progress to the save file, if the game is being saved
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.save_file {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let state = SaveState {
+            badges: self.player_badges.clone(),
+            defeated_trainers: self.defeated_trainers.clone(),
+            flags: self.flags.clone(),
+            items: self.items.clone(),
+            money: self.money,
+            completed_quests: self.quest_log.completed.clone(),
+            wild_beaten: self.quest_log.wild.clone(),
+            gym_trainers_beaten: self.quest_log.gym_trainers.clone(),
+            respawn: Some(self.respawn.clone()),
+        };
+        state.save(path)
+    }
+
+    /// Restores the health of the whole team, and undoes any changes to its stats
+    pub fn heal_team(&mut self) {
+        for item in self.battle_state.player_team.iter_mut() {
+            item.1 = 100.0;
+        }
+        self.battle_draw.player_health = 100.0;
+        self.battle_state.self_attack_stages = 0;
+        self.battle_state.self_defense_stages = 0;
+    }
+}
+
+/// The scenes currently alive; only the top one receives input and updates
+pub struct SceneStack<'a> {
+    scenes: Vec<Box<dyn Scene<'a> + 'a>>,
+}
+
+impl<'a> SceneStack<'a> {
+    pub fn new(first: Box<dyn Scene<'a> + 'a>) -> SceneStack<'a> {
+        SceneStack { scenes: vec![first] }
+    }
+
+    pub fn top(&mut self) -> &mut Box<dyn Scene<'a> + 'a> {
+        self.scenes.last_mut().expect("The scene stack is never empty while running")
+    }
+
+    pub fn top_kind(&self) -> SceneKind {
+        self.scenes.last().expect("The scene stack is never empty while running").kind()
+    }
+
+    /// Applies a transition; returns false once the game should stop
+    pub fn apply(&mut self, transition: Transition<'a>) -> bool {
+        match transition {
+            Transition::None => {}
+            Transition::Push(s) => self.scenes.push(s),
+            Transition::Pop => {
+                self.scenes.pop();
+            }
+            Transition::Replace(s) => {
+                self.scenes.pop();
+                self.scenes.push(s);
+            }
+            Transition::Reset(s) => self.scenes = s,
+            Transition::Quit => self.scenes.clear(),
+        }
+        !self.scenes.is_empty()
+    }
+
+    /// Draws the top scene, along with the scenes below it that it is overlaid on
+    pub fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, alpha: f64) -> Result<(), String> {
+        let bottom = self.scenes.iter().rposition(|s| !s.is_overlay()).unwrap_or(0);
+        for scene in self.scenes[bottom..].iter() {
+            scene.render(game, wincan, alpha)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use crate::input::{Action, Input};
+use crate::item::Item;
+use crate::scene::{Game, Scene, SceneKind, Transition};
+use crate::ui;
+
+// How many items the shop lists at once
+const SHOP_ROWS: usize = 8;
+
+/// How an amount of money is written
+pub fn describe_money(amount: u32) -> String {
+    format!("${}", amount)
+}
+
+/// The shop counter: items to buy, which grow in number with each badge, and the player's
+/// items to sell back
+pub struct ShopScene {
+    selling: bool,
+    choice: usize,
+    /// What happened when the player last bought or sold something
+    message: Option<String>,
+}
+
+impl ShopScene {
+    pub fn new() -> ShopScene {
+        ShopScene {
+            selling: false,
+            choice: 0,
+            message: None,
+        }
+    }
+
+    /// The items listed: the stock when buying, or what the player carries when selling
+    fn listed<'i>(&self, game: &Game<'i>) -> Vec<&'i Item> {
+        if self.selling {
+            game.catalog.carried(&game.items)
+        } else {
+            game.catalog.stock(game.player_badges.len())
+        }
+    }
+
+    fn buy(&mut self, game: &mut Game, item: &Item) {
+        if game.money < item.price {
+            self.message = Some(format!("You need {} for a {}.", describe_money(item.price), item.name));
+            return;
+        }
+        game.money -= item.price;
+        *game.items.entry(item.id.clone()).or_insert(0) += 1;
+        self.message = Some(format!("You bought a {}.", item.name));
+    }
+
+    fn sell(&mut self, game: &mut Game, item: &Item) {
+        if let Some(count) = game.items.get_mut(&item.id) {
+            *count -= 1;
+            if *count == 0 {
+                game.items.remove(&item.id);
+            }
+            game.money += item.sell_price();
+            self.message = Some(format!("You sold a {} for {}.", item.name, describe_money(item.sell_price())));
+        }
+    }
+}
+
+impl Default for ShopScene {
+    fn default() -> Self {
+        ShopScene::new()
+    }
+}
+
+impl<'a> Scene<'a> for ShopScene {
+    fn handle_input(&mut self, game: &mut Game<'a>, input: &Input) -> Result<Transition<'a>, String> {
+        if input.was_pressed(Action::Left) || input.was_pressed(Action::Right) {
+            self.selling = !self.selling;
+            self.choice = 0;
+            self.message = None;
+        }
+
+        let listed = self.listed(game);
+        let count = listed.len().max(1);
+        if input.was_repeated(Action::Up) {
+            self.choice = (self.choice + count - 1) % count;
+        }
+        if input.was_repeated(Action::Down) {
+            self.choice = (self.choice + 1) % count;
+        }
+        if input.was_pressed(Action::Confirm) {
+            if let Some(item) = listed.get(self.choice) {
+                if self.selling {
+                    self.sell(game, item);
+                } else {
+                    self.buy(game, item);
+                }
+            }
+            // Selling the last of an item takes it off the list
+            self.choice = self.choice.min(self.listed(game).len().saturating_sub(1));
+        }
+        if input.was_pressed(Action::Cancel) {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn update(&mut self, _game: &mut Game<'a>, _dt: f64) -> Result<Transition<'a>, String> {
+        Ok(Transition::None)
+    }
+
+    fn render(&self, game: &Game<'a>, wincan: &mut WindowCanvas, _alpha: f64) -> Result<(), String> {
+        let text_color = Color::RGB(0xbd, 0xcd, 0xde);
+        wincan.set_draw_color(Color::RGB(0x20, 0x41, 0x6a));
+        wincan.fill_rect(Rect::new(140, 40, 1000, 640))?;
+        let title = if self.selling { "Shop: Sell" } else { "Shop: Buy" };
+        ui::draw_text(wincan, &game.assets, title, text_color, Rect::new(440, 50, 400, 50))?;
+        let money = format!("Money: {}", describe_money(game.money));
+        ui::draw_text(wincan, &game.assets, &money, Color::WHITE, Rect::new(880, 60, 240, 32))?;
+
+        // The list scrolls to keep the item picked in view
+        let listed = self.listed(game);
+        let first = (self.choice + 1).saturating_sub(SHOP_ROWS);
+        for (row, item) in listed.iter().enumerate().skip(first).take(SHOP_ROWS) {
+            let y = 120 + 60 * (row - first) as i32;
+            if row == self.choice {
+                wincan.set_draw_color(Color::RGB(0xf6, 0x52, 0x41));
+                wincan.fill_rect(Rect::new(160, y - 3, 360, 54))?;
+            }
+            wincan.set_draw_color(Color::RGB(0x39, 0x7B, 0xB4));
+            wincan.fill_rect(Rect::new(163, y, 354, 48))?;
+            let price = if self.selling { item.sell_price() } else { item.price };
+            let carrying = game.items.get(&item.id).copied().unwrap_or(0);
+            let text = format!("{} x{}  {}", item.name, carrying, describe_money(price));
+            ui::draw_text(wincan, &game.assets, &text, Color::BLACK, Rect::new(175, y + 8, 330, 32))?;
+        }
+
+        let item = match listed.get(self.choice) {
+            Some(item) => item,
+            None => {
+                let empty = if self.selling { "Nothing to sell" } else { "Nothing for sale" };
+                ui::draw_text(wincan, &game.assets, empty, text_color, Rect::new(175, 120, 330, 40))?;
+                return draw_footer(wincan, game, self.message.as_deref());
+            }
+        };
+        let mut y = 120;
+        for line in ui::wrap_text(&game.assets, &item.description, 560, 30)? {
+            draw_line(wincan, game, &line, text_color, y)?;
+            y += 36;
+        }
+        draw_footer(wincan, game, self.message.as_deref())
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn kind(&self) -> SceneKind {
+        SceneKind::Shop
+    }
+}
+
+/// Draws a line of the description of an item, from the left edge of the description
+fn draw_line(wincan: &mut WindowCanvas, game: &Game, text: &str, color: Color, y: i32) -> Result<(), String> {
+    let texture = game.assets.text(text, color)?;
+    let query = texture.query();
+    let height = 30;
+    let width = ((query.width * height) / query.height).min(560);
+    wincan.copy(&texture, None, Rect::new(550, y, width, height))
+}
+
+/// Draws what happened last, or what the buttons do, along the bottom of the shop
+fn draw_footer(wincan: &mut WindowCanvas, game: &Game, message: Option<&str>) -> Result<(), String> {
+    let help = format!(
+        "{}/{}: Buy or sell  {}: Pick  {}: Leave",
+        game.bindings.describe(Action::Left),
+        game.bindings.describe(Action::Right),
+        game.bindings.describe(Action::Confirm),
+        game.bindings.describe(Action::Cancel),
+    );
+    ui::draw_text(wincan, &game.assets, message.unwrap_or(&help), Color::WHITE, Rect::new(160, 624, 960, 32))
+}
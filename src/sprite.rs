@@ -0,0 +1,277 @@
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureQuery, WindowCanvas};
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::assets::AssetManager;
+use crate::parse::parse_number;
+
+pub const SPRITE_FILE: &str = "./data/sprites.txt";
+
+// Sprites the game draws by name, which the sprite file has to have
+const REQUIRED_SPRITES: [&str; 5] = ["player", "trainer", "roaming_trainer", "leader", "monster"];
+
+// Sprites drawn over whichever image they are given, like the monsters
+const ANY_IMAGE: &str = "-";
+
+/// Which way something is looking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facing {
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// The way to face when moving in `direction`, favoring sideways; none when standing still
+    pub fn of(direction: (i32, i32)) -> Option<Facing> {
+        match direction {
+            (x, _) if x < 0 => Some(Facing::Left),
+            (x, _) if x > 0 => Some(Facing::Right),
+            (_, y) if y < 0 => Some(Facing::Up),
+            (_, y) if y > 0 => Some(Facing::Down),
+            _ => None,
+        }
+    }
+
+    /// Parses `down`, `up`, `left` or `right`
+    pub fn parse(name: &str) -> Option<Facing> {
+        [Facing::Down, Facing::Up, Facing::Left, Facing::Right]
+            .iter()
+            .copied()
+            .find(|f| f.name() == name)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Facing::Down => "down",
+            Facing::Up => "up",
+            Facing::Left => "left",
+            Facing::Right => "right",
+        }
+    }
+
+    /// The other side, for mirroring side-facing frames
+    fn mirrored(self) -> Option<Facing> {
+        match self {
+            Facing::Left => Some(Facing::Right),
+            Facing::Right => Some(Facing::Left),
+            _ => None,
+        }
+    }
+}
+
+/// One frame of an animation: where it is on the sheet, and how far to nudge it when drawn
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    column: u32,
+    row: u32,
+    dx: i32,
+    dy: i32,
+}
+
+struct Animation {
+    frames: Vec<Frame>,
+    fps: f64,
+}
+
+/// An image cut into frames of the same size, and the animations played from them
+///
+/// Animations can be drawn for each way the sprite faces, like `walk_left`, or once for all
+/// of them, like `walk`. Frames for one side are mirrored for the other when it has none,
+/// and frames for all of them are taken to face right.
+pub struct Sprite {
+    image: Option<String>,
+    frame_w: u32,
+    frame_h: u32,
+    animations: HashMap<String, Animation>,
+}
+
+impl Sprite {
+    /// The animation to play facing a way, and whether it has to be mirrored
+    fn animation(&self, name: &str, facing: Facing) -> Option<(&Animation, bool)> {
+        let faced = |f: Facing| self.animations.get(&format!("{}_{}", name, f.name()));
+        faced(facing)
+            .map(|a| (a, false))
+            .or_else(|| facing.mirrored().and_then(faced).map(|a| (a, true)))
+            .or_else(|| self.animations.get(name).map(|a| (a, facing == Facing::Left)))
+    }
+}
+
+/// Every sprite, loaded from `data/sprites.txt`
+///
+/// The file has one record per line after a header line:
+///
+/// * `sprite,name,image,frame width,frame height` - An image of `-` draws the sprite over
+///   whichever image it is given; a frame size of 0 is the whole image
+/// * `animation,sprite,name,fps,frames` - Frames are `column:row` or `column:row:dx:dy`,
+///   separated by `;`, where `dx` and `dy` nudge the frame when drawn
+///
+/// Every sprite needs an `idle` animation, which also stands in for any it is missing.
+pub struct Sprites {
+    sprites: HashMap<String, Sprite>,
+}
+
+fn parse_frame(s: &str, line_number: usize) -> Result<Frame, String> {
+    let v = s.split(':').collect::<Vec<&str>>();
+    if v.len() != 2 && v.len() != 4 {
+        return Err(format!("line {}: expected column:row or column:row:dx:dy, found {}", line_number, s));
+    }
+    Ok(Frame {
+        column: parse_number(v[0], line_number)?,
+        row: parse_number(v[1], line_number)?,
+        dx: v.get(2).map(|n| parse_number(n, line_number)).transpose()?.unwrap_or(0),
+        dy: v.get(3).map(|n| parse_number(n, line_number)).transpose()?.unwrap_or(0),
+    })
+}
+
+impl Sprites {
+    /// Loads every sprite, failing with the line of the first problem in the file
+    pub fn load(path: &str) -> Result<Sprites, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        Sprites::parse(&contents).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn parse(contents: &str) -> Result<Sprites, String> {
+        let mut sprites: HashMap<String, Sprite> = HashMap::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            match v[0] {
+                "" => {}
+                "sprite" if v.len() == 5 => {
+                    sprites.insert(v[1].to_string(), Sprite {
+                        image: Some(v[2].to_string()).filter(|i| i != ANY_IMAGE),
+                        frame_w: parse_number(v[3], line_number)?,
+                        frame_h: parse_number(v[4], line_number)?,
+                        animations: HashMap::new(),
+                    });
+                }
+                "animation" if v.len() == 5 => {
+                    let frames = v[4]
+                        .split(';')
+                        .map(|f| parse_frame(f, line_number))
+                        .collect::<Result<Vec<Frame>, String>>()?;
+                    let sprite = sprites
+                        .get_mut(v[1])
+                        .ok_or(format!("line {}: unknown sprite {}", line_number, v[1]))?;
+                    sprite.animations.insert(v[2].to_string(), Animation {
+                        frames,
+                        fps: parse_number(v[3], line_number)?,
+                    });
+                }
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        let facings = [Facing::Down, Facing::Up, Facing::Left, Facing::Right];
+        for (name, sprite) in sprites.iter() {
+            if facings.iter().any(|f| sprite.animation("idle", *f).is_none()) {
+                return Err(format!("{} needs an idle animation facing every way", name));
+            }
+        }
+        if let Some(name) = REQUIRED_SPRITES.iter().find(|s| !sprites.contains_key(**s)) {
+            return Err(format!("there is no {} sprite", name));
+        }
+
+        Ok(Sprites { sprites })
+    }
+
+    /// Every image a sprite is cut from
+    pub fn images(&self) -> Vec<String> {
+        self.sprites.values().filter_map(|s| s.image.clone()).collect()
+    }
+
+    fn get(&self, name: &str) -> &Sprite {
+        match self.sprites.get(name) {
+            Some(sprite) => sprite,
+            None => panic!("There is no {} sprite", name),
+        }
+    }
+}
+
+/// Plays the animations of a sprite: which one, which way it faces, and how far into it
+#[derive(Debug, Clone)]
+pub struct Animator {
+    sprite: String,
+    animation: String,
+    facing: Facing,
+    time: f64,
+}
+
+impl Animator {
+    /// Starts the sprite with the given name idling
+    pub fn new(sprite: &str, facing: Facing) -> Animator {
+        Animator {
+            sprite: sprite.to_string(),
+            animation: String::from("idle"),
+            facing,
+            time: 0.0,
+        }
+    }
+
+    /// Switches to another animation, starting it over unless it is already playing
+    pub fn play(&mut self, animation: &str) {
+        if self.animation != animation {
+            self.animation = animation.to_string();
+            self.time = 0.0;
+        }
+    }
+
+    pub fn face(&mut self, facing: Facing) {
+        self.facing = facing;
+    }
+
+    pub fn facing(&self) -> Facing {
+        self.facing
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        self.time += dt;
+    }
+
+    /// Walks facing `direction`, or idles facing the same way without one, for `dt` seconds
+    pub fn walk(&mut self, direction: (i32, i32), dt: f64) {
+        match Facing::of(direction) {
+            Some(facing) => {
+                self.face(facing);
+                self.play("walk");
+            }
+            None => self.play("idle"),
+        }
+        self.advance(dt);
+    }
+
+    /// The frame being shown, and whether it is mirrored
+    fn frame(&self, sprite: &Sprite) -> (Frame, bool) {
+        let (animation, mirrored) = sprite
+            .animation(&self.animation, self.facing)
+            .or_else(|| sprite.animation("idle", self.facing))
+            .unwrap();
+        let index = (self.time * animation.fps) as usize % animation.frames.len();
+        (animation.frames[index], mirrored)
+    }
+
+    /// Draws the current frame from the sprite's own image
+    pub fn draw(&self, wincan: &mut WindowCanvas, assets: &AssetManager, sprites: &Sprites, dest: Rect) -> Result<(), String> {
+        let sprite = sprites.get(&self.sprite);
+        let image = sprite.image.as_ref().ok_or(format!("The {} sprite has no image of its own", self.sprite))?;
+        self.draw_texture(wincan, assets.texture(image), sprites, dest)
+    }
+
+    /// Draws the current frame from the given image
+    pub fn draw_texture(&self, wincan: &mut WindowCanvas, texture: &Texture, sprites: &Sprites, dest: Rect) -> Result<(), String> {
+        let sprite = sprites.get(&self.sprite);
+        let (frame, mirrored) = self.frame(sprite);
+        let TextureQuery { width, height, .. } = texture.query();
+        let frame_w = if sprite.frame_w == 0 { width } else { sprite.frame_w };
+        let frame_h = if sprite.frame_h == 0 { height } else { sprite.frame_h };
+        let src = Rect::new((frame.column * frame_w) as i32, (frame.row * frame_h) as i32, frame_w, frame_h);
+        let dest = Rect::new(dest.x() + frame.dx, dest.y() + frame.dy, dest.width(), dest.height());
+        wincan.copy_ex(texture, src, dest, 0.0, None, mirrored, false)
+    }
+}
@@ -0,0 +1,487 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureQuery, WindowCanvas};
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::assets::AssetManager;
+use crate::camera::Camera;
+use crate::npc::{Behavior, NpcSpawn};
+use crate::parse::parse_number;
+use crate::player;
+use crate::sprite::Facing;
+
+// Tiles that are left empty, showing the background
+const EMPTY_TILE: char = '.';
+
+/// What a kind of tile looks like and how it can be walked on
+pub struct Tile {
+    pub image: String,
+    pub solid: bool,
+    /// The kind of place wild monsters hide in, like grass or water
+    pub zone: Option<String>,
+}
+
+/// One spot of a layer: its kind of tile, and how far it is into the patch of that tile
+///
+/// A patch of the same tile is drawn from its image as if the image were repeated across
+/// the patch, starting from the top left corner of the patch.
+struct Cell {
+    tile: char,
+    across: u32,
+    down: u32,
+}
+
+/// A building drawn over the tiles; none of them can be walked through
+pub struct Building {
+    pub image: String,
+    pub rect: Rect,
+}
+
+/// Where a door leads
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarpTarget {
+    Gym(usize),
+    Champion,
+    /// Goes into the area with the given name, a building with a map of its own, arriving at
+    /// its entrance
+    Interior(String),
+    /// Goes back out of a building, in front of the door the player came in through
+    Exit,
+    /// Walks through to the spawn point with the given name, which can be in another area
+    Spawn(String),
+}
+
+/// A door that leads somewhere else when the player interacts with it
+pub struct Warp {
+    pub target: WarpTarget,
+    pub rect: Rect,
+}
+
+/// A sign that shows a dialogue script when the player interacts with it
+pub struct Sign {
+    pub script: String,
+    pub rect: Rect,
+}
+
+/// A spot that starts an event when the player walks into it
+pub struct Trigger {
+    pub event: String,
+    pub rect: Rect,
+}
+
+/// A side of a map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    North,
+    South,
+    East,
+    West,
+}
+
+/// Leads off one side of a map into another area
+///
+/// The player comes in on the opposite side of that area, moved along the side by `offset`
+/// pixels, so that areas lined up differently can still meet.
+pub struct Edge {
+    pub side: Side,
+    pub area: String,
+    pub offset: i32,
+}
+
+/// A map made of tiles, loaded from a file in `data/maps`
+///
+/// The file has one record per line after a header line:
+///
+/// * `size,columns,rows,tile size`
+/// * `background,r,g,b` - Shown where there are no tiles
+/// * `tile,character,image,flags` - A kind of tile; flags are `solid` and `zone=name`, separated by `;`
+/// * `building,image,x,y,width,height`
+/// * `warp,target,x,y,width,height` - Targets are `gym:number`, `champion`, `interior:area`,
+///   which leads into a building at its `entrance` spawn point, `exit`, which leads back out
+///   in front of the door to the building, and `spawn:name`, which leads to the spawn point
+///   with the given name
+/// * `sign,script,x,y,width,height` - A sign showing the dialogue script with the given name
+/// * `trigger,event,x,y,width,height` - Starts the event with the given name when the player walks in
+/// * `spawn,name,x,y` - Where the player can be placed
+/// * `npc,id,x,y,facing,sight,behavior` - A trainer, who spots the player up to `sight` pixels
+///   ahead; behaviors are `stand`, `turn,facings`, `patrol,points` and `wander,distance`, as
+///   described on `Behavior`
+/// * `talk,npc,event` - Talking to the trainer starts the event with the given name, instead of
+///   the trainer speaking and battling
+/// * `edge,side,area,offset` - Walking off the `north`, `south`, `east` or `west` side leads to another area
+/// * `layer,name` - Followed by one line of tile characters per row; `.` is an empty tile
+///
+/// Layers are drawn in order, and a spot is solid or hides wild monsters if any layer says so.
+pub struct TileMap {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_size: u32,
+    background: Color,
+    tiles: HashMap<char, Tile>,
+    layers: Vec<Vec<Vec<Cell>>>,
+    pub buildings: Vec<Building>,
+    pub warps: Vec<Warp>,
+    pub signs: Vec<Sign>,
+    pub triggers: Vec<Trigger>,
+    pub npcs: Vec<NpcSpawn>,
+    /// The events started by talking to trainers, by the trainer's id
+    pub talk_events: HashMap<String, String>,
+    pub edges: Vec<Edge>,
+    spawns: HashMap<String, (i32, i32)>,
+}
+
+/// Parses the `x,y,width,height` at the end of a record
+fn parse_rect(fields: &[&str], line_number: usize) -> Result<Rect, String> {
+    if fields.len() != 4 {
+        return Err(format!("line {}: expected x,y,width,height", line_number));
+    }
+    Ok(Rect::new(
+        parse_number(fields[0], line_number)?,
+        parse_number(fields[1], line_number)?,
+        parse_number(fields[2], line_number)?,
+        parse_number(fields[3], line_number)?,
+    ))
+}
+
+fn parse_side(s: &str, line_number: usize) -> Result<Side, String> {
+    match s {
+        "north" => Ok(Side::North),
+        "south" => Ok(Side::South),
+        "east" => Ok(Side::East),
+        "west" => Ok(Side::West),
+        _ => Err(format!("line {}: unknown side {}", line_number, s)),
+    }
+}
+
+fn parse_target(s: &str, line_number: usize) -> Result<WarpTarget, String> {
+    match s.split(':').collect::<Vec<&str>>()[..] {
+        ["gym", n] => Ok(WarpTarget::Gym(parse_number(n, line_number)?)),
+        ["champion"] => Ok(WarpTarget::Champion),
+        ["interior", area] => Ok(WarpTarget::Interior(area.to_string())),
+        ["exit"] => Ok(WarpTarget::Exit),
+        ["spawn", name] => Ok(WarpTarget::Spawn(name.to_string())),
+        _ => Err(format!("line {}: unknown warp target {}", line_number, s)),
+    }
+}
+
+/// Works out how far each spot of a layer is from the left and top edges of its patch
+fn patches(layer: &[Vec<char>]) -> Vec<Vec<Cell>> {
+    let mut cells: Vec<Vec<Cell>> = Vec::new();
+    for (row, line) in layer.iter().enumerate() {
+        let mut cell_row: Vec<Cell> = Vec::new();
+        for (column, c) in line.iter().enumerate() {
+            let across = match column {
+                0 => 0,
+                _ if line[column - 1] == *c => cell_row[column - 1].across + 1,
+                _ => 0,
+            };
+            let down = match row {
+                0 => 0,
+                _ if layer[row - 1][column] == *c => cells[row - 1][column].down + 1,
+                _ => 0,
+            };
+            cell_row.push(Cell { tile: *c, across, down });
+        }
+        cells.push(cell_row);
+    }
+    cells
+}
+
+impl TileMap {
+    /// Loads a map, failing with the line of the first problem in the file
+    pub fn load(path: &str) -> Result<TileMap, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        TileMap::parse(&contents).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn parse(contents: &str) -> Result<TileMap, String> {
+        let mut map = TileMap {
+            columns: 0,
+            rows: 0,
+            tile_size: 16,
+            background: Color::BLACK,
+            tiles: HashMap::new(),
+            layers: Vec::new(),
+            buildings: Vec::new(),
+            warps: Vec::new(),
+            signs: Vec::new(),
+            triggers: Vec::new(),
+            npcs: Vec::new(),
+            talk_events: HashMap::new(),
+            edges: Vec::new(),
+            spawns: HashMap::new(),
+        };
+
+        // Rows still expected for the layer being read
+        let mut layer_rows = 0;
+        let mut layers: Vec<Vec<Vec<char>>> = Vec::new();
+
+        // Skip the header line
+        for (index, line) in contents.lines().enumerate().skip(1) {
+            let line_number = index + 1;
+            if layer_rows > 0 {
+                let row: Vec<char> = line.trim_end().chars().collect();
+                if row.len() != map.columns as usize {
+                    return Err(format!("line {}: expected {} tiles, found {}", line_number, map.columns, row.len()));
+                }
+                if let Some(c) = row.iter().find(|c| **c != EMPTY_TILE && !map.tiles.contains_key(c)) {
+                    return Err(format!("line {}: unknown tile {}", line_number, c));
+                }
+                layers.last_mut().unwrap().push(row);
+                layer_rows -= 1;
+                continue;
+            }
+
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            match v[0] {
+                "" => {}
+                "size" if v.len() == 4 => {
+                    map.columns = parse_number(v[1], line_number)?;
+                    map.rows = parse_number(v[2], line_number)?;
+                    map.tile_size = parse_number(v[3], line_number)?;
+                }
+                "background" if v.len() == 4 => {
+                    map.background = Color::RGB(
+                        parse_number(v[1], line_number)?,
+                        parse_number(v[2], line_number)?,
+                        parse_number(v[3], line_number)?,
+                    );
+                }
+                "tile" if v.len() == 4 => {
+                    let c = v[1].chars().next().filter(|c| *c != EMPTY_TILE && v[1].len() == 1);
+                    let c = c.ok_or(format!("line {}: tiles are a single character other than {}", line_number, EMPTY_TILE))?;
+                    let flags: Vec<&str> = v[3].split(';').collect();
+                    map.tiles.insert(c, Tile {
+                        image: v[2].to_string(),
+                        solid: flags.contains(&"solid"),
+                        zone: flags.iter().find_map(|f| f.strip_prefix("zone=")).map(String::from),
+                    });
+                }
+                "building" if v.len() == 6 => {
+                    map.buildings.push(Building {
+                        image: v[1].to_string(),
+                        rect: parse_rect(&v[2..], line_number)?,
+                    });
+                }
+                "warp" if v.len() == 6 => {
+                    map.warps.push(Warp {
+                        target: parse_target(v[1], line_number)?,
+                        rect: parse_rect(&v[2..], line_number)?,
+                    });
+                }
+                "sign" if v.len() == 6 => {
+                    map.signs.push(Sign {
+                        script: v[1].to_string(),
+                        rect: parse_rect(&v[2..], line_number)?,
+                    });
+                }
+                "trigger" if v.len() == 6 => {
+                    map.triggers.push(Trigger {
+                        event: v[1].to_string(),
+                        rect: parse_rect(&v[2..], line_number)?,
+                    });
+                }
+                "spawn" if v.len() == 4 => {
+                    let spawn = (parse_number(v[2], line_number)?, parse_number(v[3], line_number)?);
+                    map.spawns.insert(v[1].to_string(), spawn);
+                }
+                "npc" if v.len() == 7 || v.len() == 8 => {
+                    map.npcs.push(NpcSpawn {
+                        id: v[1].to_string(),
+                        x: parse_number(v[2], line_number)?,
+                        y: parse_number(v[3], line_number)?,
+                        facing: Facing::parse(v[4]).ok_or(format!("line {}: unknown facing {}", line_number, v[4]))?,
+                        sight: parse_number(v[5], line_number)?,
+                        behavior: Behavior::parse(v[6], v.get(7).copied()).map_err(|e| format!("line {}: {}", line_number, e))?,
+                    });
+                }
+                "talk" if v.len() == 3 => {
+                    map.talk_events.insert(v[1].to_string(), v[2].to_string());
+                }
+                "edge" if v.len() == 4 => {
+                    map.edges.push(Edge {
+                        side: parse_side(v[1], line_number)?,
+                        area: v[2].to_string(),
+                        offset: parse_number(v[3], line_number)?,
+                    });
+                }
+                "layer" if v.len() == 2 => {
+                    if map.columns == 0 || map.rows == 0 {
+                        return Err(format!("line {}: the size has to come before any layer", line_number));
+                    }
+                    layers.push(Vec::new());
+                    layer_rows = map.rows;
+                }
+                _ => return Err(format!("line {}: cannot read {}", line_number, line)),
+            }
+        }
+
+        if layer_rows > 0 {
+            return Err(format!("the last layer is missing {} rows", layer_rows));
+        }
+        if layers.is_empty() {
+            return Err(String::from("there are no layers"));
+        }
+
+        if let Some(npc) = map.talk_events.keys().find(|id| !map.npcs.iter().any(|n| n.id == **id)) {
+            return Err(format!("there is no trainer {} to talk to", npc));
+        }
+
+        map.layers = layers.iter().map(|layer| patches(layer)).collect();
+        Ok(map)
+    }
+
+    /// Every image the map draws
+    pub fn images(&self) -> Vec<String> {
+        let tiles = self.tiles.values().map(|t| t.image.clone());
+        tiles.chain(self.buildings.iter().map(|b| b.image.clone())).collect()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.columns * self.tile_size
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rows * self.tile_size
+    }
+
+    /// Where the player is placed for the spawn point with the given name, if the map has it
+    pub fn spawn(&self, name: &str) -> Option<(i32, i32)> {
+        self.spawns.get(name).copied()
+    }
+
+    /// The side of the map that `rect` has walked off of, and where it leads
+    pub fn edge_crossed(&self, rect: &Rect) -> Option<&Edge> {
+        self.edges.iter().find(|edge| match edge.side {
+            Side::North => rect.top() < 0,
+            Side::South => rect.bottom() > self.height() as i32,
+            Side::East => rect.right() > self.width() as i32,
+            Side::West => rect.left() < 0,
+        })
+    }
+
+    /// The kinds of the tiles under any part of `rect`, in every layer
+    fn tiles_under(&self, rect: &Rect) -> Vec<Option<&Tile>> {
+        let size = self.tile_size as i32;
+        let left = rect.left().max(0) / size;
+        let right = (rect.right() - 1).min(self.width() as i32 - 1) / size;
+        let top = rect.top().max(0) / size;
+        let bottom = (rect.bottom() - 1).min(self.height() as i32 - 1) / size;
+
+        let mut tiles = Vec::new();
+        for layer in self.layers.iter() {
+            for row in top..=bottom {
+                for column in left..=right {
+                    tiles.push(self.tiles.get(&layer[row as usize][column as usize].tile));
+                }
+            }
+        }
+        tiles
+    }
+
+    /// Whether `rect` leaves the map or overlaps a solid tile, a building or a sign
+    pub fn is_blocked(&self, rect: &Rect) -> bool {
+        rect.left() < 0
+            || rect.top() < 0
+            || rect.right() > self.width() as i32
+            || rect.bottom() > self.height() as i32
+            || self.buildings.iter().any(|b| player::check_collision(rect, &b.rect))
+            || self.signs.iter().any(|s| player::check_collision(rect, &s.rect))
+            || self.tiles_under(rect).iter().any(|t| t.is_some_and(|t| t.solid))
+    }
+
+    /// Every kind of place wild monsters hide in on this map
+    pub fn zones(&self) -> Vec<&str> {
+        let mut zones: Vec<&str> = self.tiles.values().filter_map(|t| t.zone.as_deref()).collect();
+        zones.sort_unstable();
+        zones.dedup();
+        zones
+    }
+
+    /// The kind of place wild monsters hide in that `rect` stands in, if all of it is on such tiles
+    ///
+    /// Standing across two kinds, like the edge of the grass by a pond, the kind under the
+    /// middle of `rect` counts.
+    pub fn encounter_zone(&self, rect: &Rect) -> Option<&str> {
+        let size = self.tile_size as i32;
+        let zone_at = |column: i32, row: i32| {
+            let spot = Rect::new(column * size, row * size, self.tile_size, self.tile_size);
+            self.tiles_under(&spot).into_iter().flatten().find_map(|t| t.zone.as_deref())
+        };
+        let columns = (rect.left().max(0) / size)..=((rect.right() - 1).max(0) / size);
+        let rows = (rect.top().max(0) / size)..=((rect.bottom() - 1).max(0) / size);
+        let covered = rows.flat_map(|row| columns.clone().map(move |column| (column, row)));
+        if !covered.into_iter().all(|(column, row)| zone_at(column, row).is_some()) {
+            return None;
+        }
+        zone_at(rect.center().x() / size, rect.center().y() / size)
+    }
+
+    /// The door that `rect` is touching, if any
+    pub fn warp_at(&self, rect: &Rect) -> Option<&Warp> {
+        self.warps.iter().find(|w| player::check_collision(rect, &w.rect))
+    }
+
+    /// The sign that `rect` is touching, if any
+    pub fn sign_at(&self, rect: &Rect) -> Option<&Sign> {
+        self.signs.iter().find(|s| player::check_collision(rect, &s.rect))
+    }
+
+    /// The index of the trigger that `rect` is in, if any
+    pub fn trigger_at(&self, rect: &Rect) -> Option<usize> {
+        self.triggers.iter().position(|t| rect.has_intersection(t.rect))
+    }
+
+    /// Draws the background, and every layer of tiles, the buildings and the signs the camera sees
+    pub fn draw(&self, wincan: &mut WindowCanvas, assets: &AssetManager, camera: &Camera) -> Result<(), String> {
+        wincan.set_draw_color(self.background);
+        wincan.clear();
+
+        let size = self.tile_size;
+        let view = camera.view();
+        let first = |edge: i32| (edge.max(0) as u32 / size) as usize;
+        let last = |edge: i32, count: u32| (edge.max(0) as u32).div_ceil(size).min(count) as usize;
+        let columns = first(view.left())..last(view.right(), self.columns);
+        let rows = first(view.top())..last(view.bottom(), self.rows);
+        for layer in self.layers.iter() {
+            for row in rows.clone() {
+                for column in columns.clone() {
+                    let cell = &layer[row][column];
+                    let tile = match self.tiles.get(&cell.tile) {
+                        Some(tile) => tile,
+                        None => continue,
+                    };
+                    let texture = assets.texture(&tile.image);
+                    let TextureQuery { width, height, .. } = texture.query();
+                    let src = Rect::new(
+                        ((cell.across % (width / size).max(1)) * size) as i32,
+                        ((cell.down % (height / size).max(1)) * size) as i32,
+                        size,
+                        size,
+                    );
+                    let pos = Rect::new((column as u32 * size) as i32, (row as u32 * size) as i32, size, size);
+                    wincan.copy(texture, src, camera.to_screen(pos))?;
+                }
+            }
+        }
+
+        for building in self.buildings.iter().filter(|b| camera.sees(&b.rect)) {
+            wincan.copy(assets.texture(&building.image), None, camera.to_screen(building.rect))?;
+        }
+
+        // Signs are a wooden board on a post
+        for sign in self.signs.iter().filter(|s| camera.sees(&s.rect)) {
+            let rect = camera.to_screen(sign.rect);
+            let board_h = rect.height() * 2 / 3;
+            wincan.set_draw_color(Color::RGB(0x5A, 0x3A, 0x1E));
+            wincan.fill_rect(Rect::new(rect.center().x() - 3, rect.y() + board_h as i32 - 2, 6, rect.height() - board_h + 2))?;
+            wincan.fill_rect(Rect::new(rect.x(), rect.y(), rect.width(), board_h))?;
+            wincan.set_draw_color(Color::RGB(0xB0, 0x7A, 0x45));
+            wincan.fill_rect(Rect::new(rect.x() + 2, rect.y() + 2, rect.width() - 4, board_h - 4))?;
+        }
+
+        Ok(())
+    }
+}
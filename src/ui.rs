@@ -0,0 +1,104 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureQuery, WindowCanvas};
+
+use crate::assets::AssetManager;
+use crate::camera::{CAM_W, CAM_H};
+
+/// Draws `text` scaled to fit and centered within `rect`
+pub fn draw_text(
+    wincan: &mut WindowCanvas,
+    assets: &AssetManager,
+    text: &str,
+    color: Color,
+    rect: Rect,
+) -> Result<(), String> {
+    let texture = assets.text(text, color)?;
+
+    let TextureQuery { width, height, .. } = texture.query();
+    let scale = (rect.width() as f32 / width as f32).min(rect.height() as f32 / height as f32);
+    let w = (width as f32 * scale) as u32;
+    let h = (height as f32 * scale) as u32;
+    let text_rect = Rect::new(
+        rect.x() + ((rect.width() - w) / 2) as i32,
+        rect.y() + ((rect.height() - h) / 2) as i32,
+        w,
+        h,
+    );
+    wincan.copy(&texture, None, text_rect)?;
+    Ok(())
+}
+
+/// Draws a white-bordered black box with a message in it
+pub fn message_panel(wincan: &mut WindowCanvas, assets: &AssetManager, message: &str, rect: Rect) -> Result<(), String> {
+    wincan.set_draw_color(Color::WHITE);
+    wincan.fill_rect(rect)?;
+    wincan.set_draw_color(Color::BLACK);
+    wincan.fill_rect(Rect::new(rect.x() + 5, rect.y() + 5, rect.width() - 10, rect.height() - 10))?;
+    draw_text(wincan, assets, message, Color::WHITE, Rect::new(rect.x() + 15, rect.y() + 25, rect.width() - 30, rect.height() - 50))?;
+    Ok(())
+}
+
+/// Splits `text` into lines no wider than `width` when drawn `line_height` pixels tall, breaking between words
+///
+/// Words too wide for a line of their own are put on one anyway.
+pub fn wrap_text(assets: &AssetManager, text: &str, width: u32, line_height: u32) -> Result<Vec<String>, String> {
+    let fits = |line: &str| -> Result<bool, String> {
+        let (w, h) = assets.font().size_of(line).map_err(|e| e.to_string())?;
+        Ok(w as f32 * line_height as f32 / h as f32 <= width as f32)
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let longer = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if line.is_empty() || fits(&longer)? {
+            line = longer;
+        } else {
+            lines.push(line);
+            line = word.to_string();
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Shows which button does what in the corner of the screen, like "F: Talk"
+pub fn draw_hint(wincan: &mut WindowCanvas, assets: &AssetManager, hint: &str) -> Result<(), String> {
+    let rect = Rect::new(CAM_W as i32 - 420, 20, 400, 50);
+    wincan.set_draw_color(Color::RGBA(0, 0, 0, 180));
+    wincan.fill_rect(rect)?;
+    draw_text(wincan, assets, hint, Color::WHITE, Rect::new(rect.x() + 10, rect.y() + 8, rect.width() - 20, rect.height() - 16))
+}
+
+/// Darkens the whole screen; `progress` goes from 0 (untouched) to 1 (black)
+pub fn draw_fade(wincan: &mut WindowCanvas, progress: f64) -> Result<(), String> {
+    let alpha = (progress.clamp(0.0, 1.0) * 255.0) as u8;
+    wincan.set_draw_color(Color::RGBA(0, 0, 0, alpha));
+    wincan.fill_rect(Rect::new(0, 0, CAM_W, CAM_H))
+}
+
+/// A fade to black that plays over a scene while the game keeps running
+pub struct Fade {
+    elapsed: f64,
+    duration: f64,
+}
+
+impl Fade {
+    /// * `duration` - Seconds until the screen is black
+    pub fn new(duration: f64) -> Fade {
+        Fade { elapsed: 0.0, duration }
+    }
+
+    /// Advances the fade by `dt` seconds; returns whether the screen has gone black
+    pub fn advance(&mut self, dt: f64) -> bool {
+        self.elapsed += dt;
+        self.elapsed >= self.duration
+    }
+
+    pub fn draw(&self, wincan: &mut WindowCanvas) -> Result<(), String> {
+        draw_fade(wincan, self.elapsed / self.duration)
+    }
+}
@@ -0,0 +1,148 @@
+use sdl2::rect::Rect;
+
+use std::fs;
+
+use crate::tilemap::{Edge, Side, TileMap, WarpTarget};
+
+pub const WORLD_FILE: &str = "./data/world.txt";
+
+// Spawn points the game looks up by name, which some area has to have
+const REQUIRED_SPAWNS: [&str; 3] = ["start", FIRST_RESPAWN, "home"];
+
+/// Where the player wakes up after blacking out, until they heal somewhere else
+pub const FIRST_RESPAWN: &str = "nurse";
+
+/// The spawn point of a building where the player arrives through its door
+pub const ENTRANCE: &str = "entrance";
+
+/// Every area of the world, like towns and routes, and how they connect
+///
+/// `data/world.txt` lists each area as `name,map file` after a header line. Areas lead
+/// into each other through the edges of their maps, and through doors. Buildings with a map
+/// of their own are areas too: doors lead into them, and their exits lead back out in front
+/// of the door.
+pub struct World {
+    areas: Vec<(String, TileMap)>,
+}
+
+impl World {
+    /// Loads every area, failing if any map cannot be read or leads somewhere that does not exist
+    pub fn load(path: &str) -> Result<World, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+
+        let mut areas = Vec::new();
+        for line in contents.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+            let v = line.trim().split(',').collect::<Vec<&str>>();
+            if v.len() != 2 {
+                return Err(format!("{}: cannot read {}", path, line));
+            }
+            areas.push((v[0].to_string(), TileMap::load(v[1])?));
+        }
+
+        let world = World { areas };
+        for (name, map) in world.areas.iter() {
+            if let Some(edge) = map.edges.iter().find(|e| world.find(&e.area).is_none()) {
+                return Err(format!("{}: {} leads to {}, which is not an area", path, name, edge.area));
+            }
+            for warp in map.warps.iter() {
+                match &warp.target {
+                    WarpTarget::Spawn(spawn) if !world.has_spawn(spawn) => {
+                        return Err(format!("{}: {} has a door to {}, which is not a spawn point", path, name, spawn));
+                    }
+                    WarpTarget::Interior(area) if world.find(area).is_none_or(|a| world.map(a).spawn(ENTRANCE).is_none()) => {
+                        return Err(format!("{}: {} has a door to {}, which is not an area with an {} spawn", path, name, area, ENTRANCE));
+                    }
+                    WarpTarget::Exit if world.find_door(name).is_none() => {
+                        return Err(format!("{}: {} has an exit, but no door leads into it", path, name));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if let Some(name) = REQUIRED_SPAWNS.iter().find(|s| world.find_spawn(s).is_none()) {
+            return Err(format!("{}: no area has a {} spawn", path, name));
+        }
+
+        Ok(world)
+    }
+
+    /// Every image drawn in any area
+    pub fn images(&self) -> Vec<String> {
+        self.areas.iter().flat_map(|(_, map)| map.images()).collect()
+    }
+
+    /// The index of the area with the given name
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.areas.iter().position(|(n, _)| n == name)
+    }
+
+    pub fn map(&self, area: usize) -> &TileMap {
+        &self.areas[area].1
+    }
+
+    pub fn name(&self, area: usize) -> &str {
+        &self.areas[area].0
+    }
+
+    /// The name and map of every area
+    pub fn areas(&self) -> impl Iterator<Item = (&str, &TileMap)> {
+        self.areas.iter().map(|(name, map)| (name.as_str(), map))
+    }
+
+    fn find_spawn(&self, name: &str) -> Option<(usize, i32, i32)> {
+        self.areas
+            .iter()
+            .enumerate()
+            .find_map(|(area, (_, map))| map.spawn(name).map(|(x, y)| (area, x, y)))
+    }
+
+    pub fn has_spawn(&self, name: &str) -> bool {
+        self.find_spawn(name).is_some()
+    }
+
+    /// The area and position of the spawn point with the given name
+    pub fn spawn(&self, name: &str) -> (usize, i32, i32) {
+        match self.find_spawn(name) {
+            Some(spawn) => spawn,
+            None => panic!("No area has a {} spawn", name),
+        }
+    }
+
+    fn find_door(&self, interior: &str) -> Option<(usize, Rect)> {
+        self.areas.iter().enumerate().find_map(|(area, (_, map))| {
+            map.warps
+                .iter()
+                .find(|w| w.target == WarpTarget::Interior(interior.to_string()))
+                .map(|w| (area, w.rect))
+        })
+    }
+
+    /// The area and door of the first door that leads into the building with the given name
+    pub fn door(&self, interior: &str) -> (usize, Rect) {
+        match self.find_door(interior) {
+            Some(door) => door,
+            None => panic!("No door leads into {}", interior),
+        }
+    }
+
+    /// The area and position of the entrance of the building with the given name
+    pub fn entrance(&self, interior: &str) -> (usize, i32, i32) {
+        let area = self.find(interior).unwrap();
+        match self.map(area).spawn(ENTRANCE) {
+            Some((x, y)) => (area, x, y),
+            None => panic!("{} has no {} spawn", interior, ENTRANCE),
+        }
+    }
+
+    /// Where `player` ends up after walking off an edge: the area, and the position on its opposite side
+    pub fn arrival(&self, edge: &Edge, player: &Rect) -> (usize, i32, i32) {
+        let area = self.find(&edge.area).unwrap();
+        let map = self.map(area);
+        match edge.side {
+            Side::North => (area, player.x() + edge.offset, (map.height() - player.height()) as i32 - 1),
+            Side::South => (area, player.x() + edge.offset, 1),
+            Side::East => (area, 1, player.y() + edge.offset),
+            Side::West => (area, (map.width() - player.width()) as i32 - 1, player.y() + edge.offset),
+        }
+    }
+}